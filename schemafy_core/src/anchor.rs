@@ -0,0 +1,386 @@
+//! Tracking of YAML anchors and the aliases that refer to them.
+//!
+//! serde_yaml expands every alias into a copy of the anchored node, so a
+//! `YamlValue` built from it can no longer tell which subtrees were shared.
+//! This module re-reads the document's event stream to recover that
+//! information. The same pass records the tags of scalars, such as
+//! `!!binary` or `!Color`, and repeated mapping keys, which serde_yaml drops
+//! as well.
+
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle, TokenType};
+
+use crate::yaml_value::YamlValue;
+
+/// A node declared with an anchor (`&name`) together with every alias
+/// (`*name`) that refers to it.
+///
+/// Nodes are identified by JSON-pointer-style paths from the document root,
+/// e.g. `/defaults/timeout` or `/servers/0`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnchorGroup {
+    /// The path of the anchored node.
+    pub anchor: String,
+    /// The paths at which the anchored node was aliased, in document order.
+    pub aliases: Vec<String>,
+}
+
+/// Returns the anchors of a single YAML document which are referenced by at
+/// least one alias, in the order the anchors were declared.
+///
+/// Anchors declared inside complex (non-scalar) mapping keys are not
+/// tracked.
+pub fn anchor_groups(input: &str) -> Result<Vec<AnchorGroup>, serde_yaml::Error> {
+    Ok(collect(input)?
+        .groups
+        .into_values()
+        .filter(|group| !group.aliases.is_empty())
+        .collect())
+}
+
+/// The tagged scalars of a single YAML document, in document order.
+pub(crate) struct ScalarTags {
+    /// The paths of the scalars tagged `!!binary`.
+    pub binaries: Vec<String>,
+    /// The paths and tags of the scalars with a custom tag, i.e. one other
+    /// than the standard `!!` tags which serde_yaml resolves by itself.
+    pub custom: Vec<(String, String)>,
+}
+
+pub(crate) fn scalar_tags(input: &str) -> Result<ScalarTags, serde_yaml::Error> {
+    let collector = collect(input)?;
+    Ok(ScalarTags {
+        binaries: collector.binaries,
+        custom: collector.custom_tags,
+    })
+}
+
+/// A mapping key which occurs more than once in the same mapping. serde_yaml
+/// silently keeps the last value. See [`YamlValue::parse_strict`].
+///
+/// [`YamlValue::parse_strict`]: crate::yaml_value::YamlValue::parse_strict
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateKeyError {
+    /// The path of the mapping containing the key, `""` for the root.
+    pub path: String,
+    /// The repeated key, as written in the document.
+    pub key: String,
+}
+
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        write!(f, "duplicate key `{}` in the mapping at `{}`", self.key, path)
+    }
+}
+
+impl Error for DuplicateKeyError {}
+
+/// Returns the scalar keys repeated within a mapping of a single YAML
+/// document, in document order. Keys are compared by the value they resolve
+/// to, so `1` and `0x1` are the same key while `1` and `"1"` are not.
+/// Complex keys are not compared.
+pub(crate) fn duplicate_keys(input: &str) -> Result<Vec<DuplicateKeyError>, serde_yaml::Error> {
+    Ok(collect(input)?.duplicates)
+}
+
+fn collect(input: &str) -> Result<Collector, serde_yaml::Error> {
+    let mut collector = Collector::default();
+    Parser::new(input.chars())
+        .load(&mut collector, false)
+        .map_err(<serde_yaml::Error as serde::de::Error>::custom)?;
+    Ok(collector)
+}
+
+/// The 1-based line and column of the first syntax error in a stream of
+/// documents, for errors which serde_yaml reports without a location.
+pub(crate) fn syntax_error_location(input: &str) -> Option<(usize, usize)> {
+    let err = Parser::new(input.chars())
+        .load(&mut Collector::default(), true)
+        .err()?;
+    Some((err.marker().line(), err.marker().col() + 1))
+}
+
+enum Frame {
+    Sequence { index: usize },
+    Mapping {
+        key: Option<String>,
+        /// The scalar keys seen so far.
+        seen: HashSet<YamlValue>,
+    },
+}
+
+#[derive(Default)]
+struct Collector {
+    frames: Vec<Frame>,
+    /// Nesting depth inside a complex mapping key, which is skipped.
+    key_depth: usize,
+    groups: BTreeMap<usize, AnchorGroup>,
+    binaries: Vec<String>,
+    custom_tags: Vec<(String, String)>,
+    duplicates: Vec<DuplicateKeyError>,
+}
+
+impl Collector {
+    fn path(&self) -> String {
+        path_of(&self.frames)
+    }
+
+    fn is_key(&self) -> bool {
+        matches!(self.frames.last(), Some(Frame::Mapping { key: None, .. }))
+    }
+
+    /// Records the scalar `key` of the innermost mapping, which resolves to
+    /// `value`.
+    fn key(&mut self, key: String, value: YamlValue) {
+        let Some((Frame::Mapping { key: current, seen }, parents)) = self.frames.split_last_mut()
+        else {
+            return;
+        };
+        if !seen.insert(value) {
+            self.duplicates.push(DuplicateKeyError {
+                path: path_of(parents),
+                key: key.clone(),
+            });
+        }
+        *current = Some(key);
+    }
+
+    fn anchor(&mut self, id: usize) {
+        if id != 0 {
+            let anchor = self.path();
+            self.groups.insert(
+                id,
+                AnchorGroup {
+                    anchor,
+                    aliases: Vec::new(),
+                },
+            );
+        }
+    }
+
+    fn complete_node(&mut self) {
+        match self.frames.last_mut() {
+            Some(Frame::Sequence { index }) => *index += 1,
+            Some(Frame::Mapping { key, .. }) => *key = None,
+            None => {}
+        }
+    }
+}
+
+fn path_of(frames: &[Frame]) -> String {
+    frames
+        .iter()
+        .map(|frame| match frame {
+            Frame::Sequence { index } => format!("/{}", index),
+            Frame::Mapping { key, .. } => format!(
+                "/{}",
+                key.as_deref()
+                    .unwrap_or("")
+                    .replace('~', "~0")
+                    .replace('/', "~1")
+            ),
+        })
+        .collect()
+}
+
+/// Resolves a scalar like serde_yaml does: quoted scalars and those tagged
+/// `!!str` are strings, and plain ones may also be null, a bool or a number.
+fn resolve_scalar(text: &str, style: TScalarStyle, tag: Option<&TokenType>) -> YamlValue {
+    let is_str = match tag {
+        Some(TokenType::Tag(handle, suffix)) => {
+            (handle == "!!" && suffix == "str")
+                || (handle.is_empty() && suffix == "tag:yaml.org,2002:str")
+        }
+        _ => false,
+    };
+    if style != TScalarStyle::Plain || is_str {
+        return YamlValue::from(text);
+    }
+    serde_yaml::from_str(text).unwrap_or_else(|_| YamlValue::from(text))
+}
+
+fn is_binary_tag(tag: Option<&TokenType>) -> bool {
+    match tag {
+        Some(TokenType::Tag(handle, suffix)) => {
+            (handle == "!!" && suffix == "binary")
+                || (handle.is_empty() && suffix == "tag:yaml.org,2002:binary")
+        }
+        _ => false,
+    }
+}
+
+/// Returns the tag as written, e.g. `!Color` or `!!python/object`, unless it
+/// is a standard tag of the YAML core schema or the non-specific `!`.
+fn custom_tag(tag: Option<&TokenType>) -> Option<String> {
+    const STANDARD: &[&str] = &[
+        "binary",
+        "bool",
+        "float",
+        "int",
+        "map",
+        "null",
+        "seq",
+        "str",
+        "timestamp",
+    ];
+    let (handle, suffix) = match tag {
+        Some(TokenType::Tag(handle, suffix)) if !suffix.is_empty() => (handle, suffix),
+        _ => return None,
+    };
+    let standard = match handle.as_str() {
+        "!!" => Some(suffix.as_str()),
+        "" => suffix.strip_prefix("tag:yaml.org,2002:"),
+        _ => None,
+    };
+    if standard.is_some_and(|name| STANDARD.contains(&name)) {
+        None
+    } else if handle.is_empty() {
+        Some(format!("!<{}>", suffix))
+    } else {
+        Some(format!("{}{}", handle, suffix))
+    }
+}
+
+impl MarkedEventReceiver for Collector {
+    fn on_event(&mut self, event: Event, _mark: Marker) {
+        if self.key_depth > 0 {
+            match event {
+                Event::SequenceStart(_) | Event::MappingStart(_) => self.key_depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => {
+                    self.key_depth -= 1;
+                    if self.key_depth == 0 {
+                        if let Some(Frame::Mapping { key, .. }) = self.frames.last_mut() {
+                            *key = Some(String::new());
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Scalar(text, style, id, tag) => {
+                if self.is_key() {
+                    let value = resolve_scalar(&text, style, tag.as_ref());
+                    self.key(text, value);
+                } else {
+                    if is_binary_tag(tag.as_ref()) {
+                        self.binaries.push(self.path());
+                    } else if let Some(tag) = custom_tag(tag.as_ref()) {
+                        self.custom_tags.push((self.path(), tag));
+                    }
+                    self.anchor(id);
+                    self.complete_node();
+                }
+            }
+            Event::Alias(id) => {
+                if self.is_key() {
+                    if let Some(Frame::Mapping { key, .. }) = self.frames.last_mut() {
+                        *key = Some(String::new());
+                    }
+                } else {
+                    let path = self.path();
+                    if let Some(group) = self.groups.get_mut(&id) {
+                        group.aliases.push(path);
+                    }
+                    self.complete_node();
+                }
+            }
+            Event::SequenceStart(_) | Event::MappingStart(_) if self.is_key() => {
+                self.key_depth = 1;
+            }
+            Event::SequenceStart(id) => {
+                self.anchor(id);
+                self.frames.push(Frame::Sequence { index: 0 });
+            }
+            Event::MappingStart(id) => {
+                self.anchor(id);
+                self.frames.push(Frame::Mapping {
+                    key: None,
+                    seen: HashSet::new(),
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.frames.pop();
+                self.complete_node();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliased_document() {
+        let input = r#"
+defaults: &defaults
+  timeout: 30
+servers:
+  - name: a
+    settings: *defaults
+  - name: b
+    settings: *defaults
+retries: &retries 3
+fallback: *retries
+unused: &unused 1
+"#;
+        assert_eq!(
+            anchor_groups(input).unwrap(),
+            vec![
+                AnchorGroup {
+                    anchor: "/defaults".to_string(),
+                    aliases: vec![
+                        "/servers/0/settings".to_string(),
+                        "/servers/1/settings".to_string(),
+                    ],
+                },
+                AnchorGroup {
+                    anchor: "/retries".to_string(),
+                    aliases: vec!["/fallback".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn deeply_nested_alias() {
+        let input = "a: [[[[{ b: &x [1, 2] }]]]]\nc: [[*x]]\n";
+        assert_eq!(
+            anchor_groups(input).unwrap(),
+            vec![AnchorGroup {
+                anchor: "/a/0/0/0/0/b".to_string(),
+                aliases: vec!["/c/0/0".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn binary_scalars() {
+        let input = "a: !!binary aGk=\nb: [1, !!binary |\n    aGk=\n]\nc: aGk=\n";
+        assert_eq!(scalar_tags(input).unwrap().binaries, vec!["/a", "/b/1"]);
+    }
+
+    #[test]
+    fn custom_scalar_tags() {
+        let input = "a: !Color red\nb: [!!str 1, !!python/object x]\nc: !<tag:x.org,2024:y> z\n";
+        let tags = scalar_tags(input).unwrap();
+        assert!(tags.binaries.is_empty());
+        assert_eq!(
+            tags.custom,
+            vec![
+                ("/a".to_string(), "!Color".to_string()),
+                ("/b/1".to_string(), "!!python/object".to_string()),
+                ("/c".to_string(), "!<tag:x.org,2024:y>".to_string()),
+            ]
+        );
+    }
+}
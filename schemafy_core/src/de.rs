@@ -0,0 +1,153 @@
+//! Decoding Rust types straight from a `YamlValue`.
+//!
+//! `&YamlValue` implements `serde::Deserializer`, so any `Deserialize` type,
+//! including the generated ones, can be built from a parsed value without
+//! first copying it into a `serde_yaml::Value`.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+
+use crate::yaml_value::YamlValue;
+
+/// Types which can be decoded from a borrowed `YamlValue`. Implemented for
+/// every `DeserializeOwned` type, which includes all generated types.
+pub trait FromYamlValue: Sized {
+    fn from_yaml_value(value: &YamlValue) -> Result<Self, serde_yaml::Error>;
+}
+
+impl<T: DeserializeOwned> FromYamlValue for T {
+    fn from_yaml_value(value: &YamlValue) -> Result<Self, serde_yaml::Error> {
+        T::deserialize(value)
+    }
+}
+
+/// Decodes like `serde_yaml::from_value` does: `Bytes` are seen as base64
+/// strings and tags are ignored.
+impl<'de> Deserializer<'de> for &'de YamlValue {
+    type Error = serde_yaml::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            YamlValue::Null => visitor.visit_unit(),
+            YamlValue::Bool(value) => visitor.visit_bool(*value),
+            YamlValue::Number(number) => {
+                if let Some(value) = number.as_u64() {
+                    visitor.visit_u64(value)
+                } else if let Some(value) = number.as_i64() {
+                    visitor.visit_i64(value)
+                } else {
+                    visitor.visit_f64(number.as_f64().unwrap())
+                }
+            }
+            YamlValue::String(value) => visitor.visit_borrowed_str(value),
+            YamlValue::Bytes(bytes) => visitor.visit_string(BASE64.encode(bytes)),
+            YamlValue::Sequence(seq) => {
+                let mut deserializer = SeqDeserializer::new(seq.iter());
+                let value = visitor.visit_seq(&mut deserializer)?;
+                deserializer.end()?;
+                Ok(value)
+            }
+            YamlValue::Mapping(map) => {
+                let mut deserializer = MapDeserializer::new(map.iter());
+                let value = visitor.visit_map(&mut deserializer)?;
+                deserializer.end()?;
+                Ok(value)
+            }
+            YamlValue::Tagged { value, .. } => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.untagged() {
+            YamlValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Accepts a unit variant as a string, and any other variant as a
+    /// mapping with the variant name as its single key.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.untagged() {
+            YamlValue::String(variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+            YamlValue::Mapping(map) if map.len() == 1 => {
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(map.iter())))
+            }
+            _ => Err(serde::de::Error::custom(
+                "expected a string or a mapping with a single key for an enum",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, serde_yaml::Error> for &'de YamlValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Shape {
+        Point,
+        Circle { radius: f64 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Drawing {
+        name: String,
+        shapes: Vec<Shape>,
+        scale: Option<u32>,
+        #[serde(flatten)]
+        rest: std::collections::BTreeMap<String, i64>,
+    }
+
+    #[test]
+    fn matches_serde_yaml() {
+        let value: YamlValue = serde_yaml::from_str(
+            "name: d\nshapes: [point, {circle: {radius: 1}}]\nscale: null\nextra: 7\n",
+        )
+        .unwrap();
+        let drawing = Drawing::from_yaml_value(&value).unwrap();
+        assert_eq!(
+            drawing,
+            serde_yaml::from_value(serde_yaml::Value::from(&value)).unwrap()
+        );
+        assert_eq!(drawing.shapes[1], Shape::Circle { radius: 1.0 });
+        assert_eq!(drawing.rest["extra"], 7);
+    }
+
+    #[test]
+    fn reports_type_errors() {
+        let value = crate::yaml!({"name": 1, "shapes": []});
+        assert!(Drawing::from_yaml_value(&value).is_err());
+        assert!(u8::from_yaml_value(&YamlValue::from(300)).is_err());
+    }
+}
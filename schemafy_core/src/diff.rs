@@ -0,0 +1,270 @@
+//! Structural diffs between two `YamlValue`s.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::path::PathSegment;
+use crate::yaml_value::YamlValue;
+
+/// A single difference between two values, as produced by
+/// [`YamlValue::diff`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A mapping entry or sequence element exists only in the new value.
+    Added {
+        path: Vec<PathSegment>,
+        value: YamlValue,
+    },
+    /// A mapping entry or sequence element exists only in the old value.
+    Removed { path: Vec<PathSegment> },
+    /// The node at `path` differs between the two values.
+    Modified {
+        path: Vec<PathSegment>,
+        from: YamlValue,
+        to: YamlValue,
+    },
+}
+
+/// The error returned by [`YamlValue::apply`] when a change cannot be
+/// applied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchError {
+    /// The path of the change which could not be applied.
+    pub path: Vec<PathSegment>,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("cannot apply change at `")?;
+        for segment in &self.path {
+            write!(f, "/{}", segment)?;
+        }
+        f.write_str("`: path does not exist")
+    }
+}
+
+impl Error for PatchError {}
+
+impl YamlValue {
+    /// Computes the changes needed to turn `self` into `other`.
+    ///
+    /// Mappings are matched up by key and sequences by index, recursing into
+    /// nodes present on both sides. When a sequence shrinks, the trailing
+    /// elements are reported as removed from the highest index down, and when
+    /// it grows the new elements are reported as added in ascending order.
+    pub fn diff(&self, other: &YamlValue) -> Vec<Change> {
+        let mut changes = Vec::new();
+        diff_into(&mut Vec::new(), self, other, &mut changes);
+        changes
+    }
+
+    /// Applies changes produced by [`YamlValue::diff`] in order, so that
+    /// `a.apply(&a.diff(&b))` turns `a` into a value equal to `b`.
+    ///
+    /// Fails if a `Modified` or `Removed` change targets a node which does not
+    /// exist, or if an `Added` change targets a missing parent or an index
+    /// past the end of a sequence. Changes before the failing one remain
+    /// applied.
+    pub fn apply(&mut self, changes: &[Change]) -> Result<(), PatchError> {
+        for change in changes {
+            apply_change(self, change).ok_or_else(|| PatchError {
+                path: match change {
+                    Change::Added { path, .. }
+                    | Change::Removed { path }
+                    | Change::Modified { path, .. } => path.clone(),
+                },
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn apply_change(target: &mut YamlValue, change: &Change) -> Option<()> {
+    match change {
+        Change::Modified { path, to, .. } => {
+            *target.get_path_mut(path)? = to.clone();
+        }
+        Change::Added { path, value } => match path.split_last() {
+            None => *target = value.clone(),
+            Some((last, parent)) => match (target.get_path_mut(parent)?, last) {
+                (YamlValue::Mapping(map), PathSegment::Key(key)) => {
+                    map.insert(key.clone(), value.clone());
+                }
+                (YamlValue::Sequence(seq), PathSegment::Index(index)) if *index <= seq.len() => {
+                    seq.insert(*index, value.clone());
+                }
+                _ => return None,
+            },
+        },
+        Change::Removed { path } => {
+            let (last, parent) = path.split_last()?;
+            match (target.get_path_mut(parent)?, last) {
+                (YamlValue::Mapping(map), PathSegment::Key(key)) => {
+                    map.shift_remove(key)?;
+                }
+                (YamlValue::Sequence(seq), PathSegment::Index(index)) if *index < seq.len() => {
+                    seq.remove(*index);
+                }
+                _ => return None,
+            }
+        }
+    }
+    Some(())
+}
+
+fn diff_into(
+    path: &mut Vec<PathSegment>,
+    from: &YamlValue,
+    to: &YamlValue,
+    changes: &mut Vec<Change>,
+) {
+    match (from, to) {
+        (YamlValue::Mapping(from), YamlValue::Mapping(to)) => {
+            for (key, from_value) in from {
+                path.push(PathSegment::Key(key.clone()));
+                match to.get(key) {
+                    Some(to_value) => diff_into(path, from_value, to_value, changes),
+                    None => changes.push(Change::Removed { path: path.clone() }),
+                }
+                path.pop();
+            }
+            for (key, to_value) in to {
+                if !from.contains_key(key) {
+                    path.push(PathSegment::Key(key.clone()));
+                    changes.push(Change::Added {
+                        path: path.clone(),
+                        value: to_value.clone(),
+                    });
+                    path.pop();
+                }
+            }
+        }
+        (YamlValue::Sequence(from), YamlValue::Sequence(to)) => {
+            for (index, (from_value, to_value)) in from.iter().zip(to).enumerate() {
+                path.push(PathSegment::Index(index));
+                diff_into(path, from_value, to_value, changes);
+                path.pop();
+            }
+            for index in (to.len()..from.len()).rev() {
+                path.push(PathSegment::Index(index));
+                changes.push(Change::Removed { path: path.clone() });
+                path.pop();
+            }
+            for (index, to_value) in to.iter().enumerate().skip(from.len()) {
+                path.push(PathSegment::Index(index));
+                changes.push(Change::Added {
+                    path: path.clone(),
+                    value: to_value.clone(),
+                });
+                path.pop();
+            }
+        }
+        (from, to) if from != to => changes.push(Change::Modified {
+            path: path.clone(),
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> YamlValue {
+        serde_yaml::from_str(input).unwrap()
+    }
+
+    #[test]
+    fn diff_mappings() {
+        let a = parse("{keep: 1, gone: 2, nested: {leaf: a}}");
+        let b = parse("{keep: 1, nested: {leaf: b}, new: 3}");
+        assert_eq!(
+            a.diff(&b),
+            vec![
+                Change::Removed {
+                    path: vec!["gone".into()],
+                },
+                Change::Modified {
+                    path: vec!["nested".into(), "leaf".into()],
+                    from: YamlValue::from("a"),
+                    to: YamlValue::from("b"),
+                },
+                Change::Added {
+                    path: vec!["new".into()],
+                    value: YamlValue::from(3),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_round_trip() {
+        let a = parse("{keep: 1, gone: [1, 2, 3], nested: {leaf: a, list: [x]}}");
+        let b = parse("{keep: 1, gone: [1], nested: {leaf: b, list: [x, y, z]}, new: 3}");
+        let mut patched = a.clone();
+        patched.apply(&a.diff(&b)).unwrap();
+        assert_eq!(patched, b);
+
+        let mut patched = b.clone();
+        patched.apply(&b.diff(&a)).unwrap();
+        assert_eq!(patched, a);
+    }
+
+    #[test]
+    fn apply_to_missing_path() {
+        let mut value = parse("{a: 1}");
+        let change = Change::Removed {
+            path: vec!["b".into()],
+        };
+        assert_eq!(
+            value.apply(&[change]),
+            Err(PatchError {
+                path: vec!["b".into()],
+            })
+        );
+        let change = Change::Modified {
+            path: vec!["a".into(), 0.into()],
+            from: YamlValue::from(1),
+            to: YamlValue::from(2),
+        };
+        assert!(value.apply(&[change]).is_err());
+    }
+
+    #[test]
+    fn apply_keeps_mapping_order() {
+        let mut value = parse("{a: 1, b: 2, c: 3, d: 4}");
+        let change = Change::Removed {
+            path: vec!["b".into()],
+        };
+        value.apply(&[change]).unwrap();
+        assert_eq!(value.to_string_compact(), "{a: 1, c: 3, d: 4}");
+    }
+
+    #[test]
+    fn diff_sequences() {
+        let a = parse("[1, 2, 3]");
+        assert_eq!(
+            a.diff(&parse("[1, 5]")),
+            vec![
+                Change::Modified {
+                    path: vec![1.into()],
+                    from: YamlValue::from(2),
+                    to: YamlValue::from(5),
+                },
+                Change::Removed {
+                    path: vec![2.into()],
+                },
+            ]
+        );
+        assert_eq!(
+            a.diff(&parse("[1, 2, 3, 4]")),
+            vec![Change::Added {
+                path: vec![3.into()],
+                value: YamlValue::from(4),
+            }]
+        );
+        assert!(a.diff(&a).is_empty());
+    }
+}
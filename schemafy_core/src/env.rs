@@ -0,0 +1,296 @@
+//! Interpolation of environment variables into string values, and
+//! flattening of values into environment-style maps.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::yaml_value::YamlValue;
+
+/// An error produced by [`YamlValue::expand_env`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnvError {
+    /// The variable is not set, or is not valid unicode.
+    Undefined(String),
+    /// The string has a `${` without a matching `}`.
+    Unclosed(String),
+    /// The string has a `${...}` whose name is empty or not a valid name.
+    InvalidName(String),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvError::Undefined(name) => write!(f, "environment variable `{}` is not set", name),
+            EnvError::Unclosed(value) => write!(f, "unclosed `${{` in `{}`", value),
+            EnvError::InvalidName(value) => write!(f, "invalid variable name in `{}`", value),
+        }
+    }
+}
+
+impl Error for EnvError {}
+
+impl YamlValue {
+    /// Recursively replaces `${NAME}` and `$NAME` in every `String` with the
+    /// value of the environment variable `NAME`, failing on the first
+    /// variable which is not set. Names start with a letter or `_`, followed
+    /// by letters, digits and `_`. `$$` stands for a literal `$`, as does a
+    /// `$` not followed by a name, such as in `$5`, while `${}` or `${5}` is
+    /// an error. Mapping keys are not touched.
+    ///
+    /// On error, the strings visited before the failing one have already
+    /// been expanded.
+    pub fn expand_env(&mut self) -> Result<(), EnvError> {
+        self.expand_env_with(&mut |name| std::env::var(name).ok())
+    }
+
+    /// Like [`expand_env`](Self::expand_env), but looks variables up with
+    /// `lookup` instead of in the environment. A lookup returning `None`
+    /// fails with [`EnvError::Undefined`].
+    pub fn expand_env_with<F>(&mut self, lookup: &mut F) -> Result<(), EnvError>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        self.expand_env_(lookup, false)
+    }
+
+    /// Like [`expand_env`](Self::expand_env), but leaves references to
+    /// variables which are not set as they are written, e.g. `${HOME}`.
+    pub fn expand_env_keep_undefined(&mut self) -> Result<(), EnvError> {
+        self.expand_env_(&mut |name| std::env::var(name).ok(), true)
+    }
+
+    fn expand_env_<F>(&mut self, lookup: &mut F, keep_undefined: bool) -> Result<(), EnvError>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        match self {
+            YamlValue::String(value) if value.contains('$') => {
+                *value = expand(value, lookup, keep_undefined)?;
+            }
+            YamlValue::Sequence(seq) => {
+                for value in seq {
+                    value.expand_env_(lookup, keep_undefined)?;
+                }
+            }
+            YamlValue::Mapping(map) => {
+                for (_, value) in map.iter_mut() {
+                    value.expand_env_(lookup, keep_undefined)?;
+                }
+            }
+            YamlValue::Tagged { value, .. } => value.expand_env_(lookup, keep_undefined)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Flattens the tree into a map from the path of every leaf, with the
+    /// segments joined by `separator`, to the leaf as a string, e.g. for
+    /// exporting as environment variables. Keys are not changed in case.
+    ///
+    /// Sequence items are keyed by their index, so `{"hosts": ["a", "b"]}`
+    /// gives `hosts.0=a` and `hosts.1=b` with a `.` separator. Numbers and
+    /// booleans are formatted as in YAML, `Null` becomes an empty string,
+    /// `Bytes` become base64 and tags are dropped. Empty sequences and
+    /// mappings give no entries, and a scalar at the root is keyed by the
+    /// empty string. Keys which are not strings are written in flow style.
+    /// Leaves whose paths join to the same key, such as `{"a__b": 1}` and
+    /// `{"a": {"b": 2}}` with a `__` separator, are not detected: the leaf
+    /// which comes last in the document wins.
+    pub fn to_env_map(&self, separator: &str) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        flatten(self, String::new(), separator, &mut map);
+        map
+    }
+}
+
+fn flatten(value: &YamlValue, prefix: String, separator: &str, map: &mut HashMap<String, String>) {
+    let child = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}{}{}", prefix, separator, name)
+        }
+    };
+    match value {
+        YamlValue::Sequence(seq) => {
+            for (index, item) in seq.iter().enumerate() {
+                flatten(item, child(&index.to_string()), separator, map);
+            }
+        }
+        YamlValue::Mapping(mapping) => {
+            for (key, item) in mapping.iter() {
+                let name = match key.untagged() {
+                    YamlValue::String(key) => Cow::Borrowed(key.as_str()),
+                    key => Cow::Owned(key.to_string_compact()),
+                };
+                flatten(item, child(&name), separator, map);
+            }
+        }
+        YamlValue::Tagged { value, .. } => flatten(value, prefix, separator, map),
+        YamlValue::Null => {
+            map.insert(prefix, String::new());
+        }
+        scalar => {
+            let text = scalar.to_cow_str().unwrap_or_default().into_owned();
+            map.insert(prefix, text);
+        }
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn expand<F>(input: &str, lookup: &mut F, keep_undefined: bool) -> Result<String, EnvError>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(index) = rest.find('$') {
+        output.push_str(&rest[..index]);
+        let reference = &rest[index..];
+        rest = &rest[index + 1..];
+        let name = if let Some(after) = rest.strip_prefix('$') {
+            rest = after;
+            None
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| EnvError::Unclosed(input.to_string()))?;
+            let name = &after[..end];
+            if !name.starts_with(is_name_start) || !name.chars().all(is_name_char) {
+                return Err(EnvError::InvalidName(input.to_string()));
+            }
+            rest = &after[end + 1..];
+            Some(name)
+        } else if rest.starts_with(is_name_start) {
+            let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+            Some(name)
+        } else {
+            None
+        };
+        match name {
+            Some(name) => match lookup(name) {
+                Some(value) => output.push_str(&value),
+                None if keep_undefined => {
+                    output.push_str(&reference[..reference.len() - rest.len()])
+                }
+                None => return Err(EnvError::Undefined(name.to_string())),
+            },
+            None => output.push('$'),
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_defined_variables() {
+        std::env::set_var("SCHEMAFY_TEST_HOST", "example.com");
+        let mut value = crate::yaml!({
+            "url": "https://${SCHEMAFY_TEST_HOST}:8080/",
+            "hosts": ["$SCHEMAFY_TEST_HOST", "${SCHEMAFY_TEST_HOST}s"],
+            "port": 8080
+        });
+        value.expand_env().unwrap();
+        assert_eq!(
+            value,
+            crate::yaml!({
+                "url": "https://example.com:8080/",
+                "hosts": ["example.com", "example.coms"],
+                "port": 8080
+            })
+        );
+    }
+
+    #[test]
+    fn undefined_variable() {
+        let mut value = crate::yaml!({"password": "${SCHEMAFY_TEST_UNSET}"});
+        let err = value.expand_env().unwrap_err();
+        assert_eq!(err, EnvError::Undefined("SCHEMAFY_TEST_UNSET".into()));
+        assert_eq!(
+            err.to_string(),
+            "environment variable `SCHEMAFY_TEST_UNSET` is not set"
+        );
+
+        let mut value = YamlValue::from("${SCHEMAFY_TEST_UNSET");
+        assert!(matches!(value.expand_env(), Err(EnvError::Unclosed(_))));
+    }
+
+    #[test]
+    fn escaped_dollar_sign() {
+        let mut value = YamlValue::from("costs $$5 or $ 6, ${A}$B$");
+        value
+            .expand_env_with(&mut |name| Some(name.to_lowercase()))
+            .unwrap();
+        assert_eq!(value, YamlValue::from("costs $5 or $ 6, ab$"));
+    }
+
+    #[test]
+    fn to_env_map() {
+        let value = crate::yaml!({
+            "database": {"host": "db", "port": 5432, "tls": false, "password": null},
+            "hosts": ["a", "b"],
+            "empty": [],
+            "7": 1.5
+        });
+        let map = value.to_env_map("__");
+        let expected: HashMap<String, String> = [
+            ("database__host", "db"),
+            ("database__port", "5432"),
+            ("database__tls", "false"),
+            ("database__password", ""),
+            ("hosts__0", "a"),
+            ("hosts__1", "b"),
+            ("7", "1.5"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(map, expected);
+        assert_eq!(YamlValue::from("x").to_env_map(".")[""], "x");
+
+        let colliding = crate::yaml!({"a__b": 1, "a": {"b": 2}});
+        assert_eq!(colliding.to_env_map("__")["a__b"], "2");
+    }
+
+    #[test]
+    fn invalid_names() {
+        let mut value = YamlValue::from("costs $5, $_A and $9b");
+        value
+            .expand_env_with(&mut |name| Some(name.to_lowercase()))
+            .unwrap();
+        assert_eq!(value, YamlValue::from("costs $5, _a and $9b"));
+
+        for input in &["${}", "x${5}", "${A-B}"] {
+            let mut value = YamlValue::from(*input);
+            let err = value.expand_env_with(&mut |_| Some(String::new()));
+            assert_eq!(err, Err(EnvError::InvalidName(input.to_string())));
+        }
+    }
+
+    #[test]
+    fn keep_undefined_variables() {
+        std::env::set_var("SCHEMAFY_TEST_USER", "alice");
+        let mut value =
+            YamlValue::from("${SCHEMAFY_TEST_USER}:$SCHEMAFY_TEST_UNSET@${SCHEMAFY_TEST_UNSET}/$$");
+        value.expand_env_keep_undefined().unwrap();
+        assert_eq!(
+            value,
+            YamlValue::from("alice:$SCHEMAFY_TEST_UNSET@${SCHEMAFY_TEST_UNSET}/$")
+        );
+    }
+}
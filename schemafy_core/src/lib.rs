@@ -1 +1 @@
-pub mod one_or_many;pub mod yaml_value;pub mod mapping;
\ No newline at end of file
+pub mod one_or_many;pub mod yaml_value;pub mod mapping;pub mod number;pub mod anchor;pub mod path;pub mod diff;pub mod merge;pub mod shared;pub mod stats;pub mod validate;pub mod de;pub mod env;mod macros;
\ No newline at end of file
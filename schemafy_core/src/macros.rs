@@ -0,0 +1,131 @@
+//! The `yaml!` macro for building `YamlValue` literals.
+
+/// Constructs a [`YamlValue`](crate::yaml_value::YamlValue) from a
+/// `serde_json::json!`-like literal syntax.
+///
+/// ```
+/// use schemafy_core::yaml;
+/// use schemafy_core::yaml_value::YamlValue;
+///
+/// let value = yaml!({
+///     "name": "schemafy",
+///     "tags": ["yaml", "schema"],
+///     "version": 6,
+///     "parent": null,
+/// });
+/// assert!(matches!(value, YamlValue::Mapping(_)));
+/// ```
+///
+/// Mapping keys must be a single token, e.g. a literal or a parenthesized
+/// expression. Values can be any expression convertible with
+/// `YamlValue::from`.
+#[macro_export]
+macro_rules! yaml {
+    (null) => {
+        $crate::yaml_value::YamlValue::Null
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::yaml_value::YamlValue::Sequence($crate::yaml_internal!(@seq [] $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::yaml_value::YamlValue::Mapping(
+            <$crate::mapping::Mapping as ::std::iter::FromIterator<_>>::from_iter(
+                $crate::yaml_internal!(@map [] $($tt)*)
+            )
+        )
+    };
+    ($other:expr) => {
+        $crate::yaml_value::YamlValue::from($other)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! yaml_internal {
+    // Sequence elements, munched one at a time.
+    (@seq [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@seq [$($elems:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml!(null),] $($($rest)*)?)
+    };
+    (@seq [$($elems:expr,)*] [$($seq:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml!([$($seq)*]),] $($($rest)*)?)
+    };
+    (@seq [$($elems:expr,)*] {$($map:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml!({$($map)*}),] $($($rest)*)?)
+    };
+    (@seq [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml!($next),] $($rest)*)
+    };
+    (@seq [$($elems:expr,)*] $last:expr) => {
+        $crate::yaml_internal!(@seq [$($elems,)* $crate::yaml!($last),])
+    };
+
+    // Mapping entries, munched one `key: value` pair at a time.
+    (@map [$($entries:expr,)*]) => {
+        vec![$($entries,)*]
+    };
+    (@map [$($entries:expr,)*] $key:tt : null $(, $($rest:tt)*)?) => {
+        $crate::yaml_internal!(@map [$($entries,)* ($crate::yaml!($key), $crate::yaml!(null)),] $($($rest)*)?)
+    };
+    (@map [$($entries:expr,)*] $key:tt : [$($seq:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::yaml_internal!(@map [$($entries,)* ($crate::yaml!($key), $crate::yaml!([$($seq)*])),] $($($rest)*)?)
+    };
+    (@map [$($entries:expr,)*] $key:tt : {$($map:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::yaml_internal!(@map [$($entries,)* ($crate::yaml!($key), $crate::yaml!({$($map)*})),] $($($rest)*)?)
+    };
+    (@map [$($entries:expr,)*] $key:tt : $value:expr, $($rest:tt)*) => {
+        $crate::yaml_internal!(@map [$($entries,)* ($crate::yaml!($key), $crate::yaml!($value)),] $($rest)*)
+    };
+    (@map [$($entries:expr,)*] $key:tt : $value:expr) => {
+        $crate::yaml_internal!(@map [$($entries,)* ($crate::yaml!($key), $crate::yaml!($value)),])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mapping::Mapping;
+    use crate::yaml_value::YamlValue;
+
+    fn parse(input: &str) -> YamlValue {
+        serde_yaml::from_str(input).unwrap()
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(yaml!(null), YamlValue::Null);
+        assert_eq!(yaml!(true), YamlValue::Bool(true));
+        assert_eq!(yaml!(-3), YamlValue::from(-3));
+        assert_eq!(yaml!("abc"), YamlValue::String("abc".to_string()));
+    }
+
+    #[test]
+    fn sequence_matches_parsed() {
+        assert_eq!(yaml!([1, "two", null, [3, 4],]), parse("[1, two, null, [3, 4]]"));
+        assert_eq!(yaml!([]), YamlValue::Sequence(vec![]));
+    }
+
+    #[test]
+    fn mapping_matches_parsed() {
+        let n = 2;
+        let value = yaml!({
+            "a": 1,
+            "b": [n, n + 1],
+            "c": { "d": null, "e": "f" },
+            "g": -5
+        });
+        let parsed = parse(
+            r#"
+a: 1
+b: [2, 3]
+c:
+  d: ~
+  e: f
+g: -5
+"#,
+        );
+        assert_eq!(value, parsed);
+        assert_eq!(yaml!({}), YamlValue::Mapping(Mapping::new()));
+    }
+}
@@ -8,7 +8,7 @@ use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::ops::{Index, IndexMut};
-use crate::yaml_value::YamlValue;
+use crate::yaml_value::{ValueVisitor, YamlValue, MAX_DEPTH};
 
 /// A YAML mapping in which the keys and values are both `YamlValue`.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -16,6 +16,14 @@ pub struct Mapping {
     map: IndexMap<YamlValue, YamlValue>,
 }
 
+/// A single mapping entry as a `{key, value}` record, for formats and APIs
+/// which represent a mapping as a list. See [`Mapping::to_kv_list`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyValue {
+    pub key: YamlValue,
+    pub value: YamlValue,
+}
+
 impl Mapping {
     /// Creates an empty YAML map.
     #[inline]
@@ -23,6 +31,12 @@ impl Mapping {
         Self::default()
     }
 
+    /// Creates a YAML map from an array of key-value pairs, in order. Same as
+    /// `Mapping::from(pairs)`.
+    pub fn from_pairs<const N: usize>(pairs: [(YamlValue, YamlValue); N]) -> Self {
+        Mapping::from(pairs)
+    }
+
     /// Creates an empty YAML map with the given initial capacity.
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
@@ -31,6 +45,17 @@ impl Mapping {
         }
     }
 
+    /// Creates a YAML map from key-value pairs like `from_iter`, but reserves
+    /// room for `capacity` entries up front instead of growing as it goes.
+    pub fn from_iter_with_capacity<I>(iter: I, capacity: usize) -> Self
+    where
+        I: IntoIterator<Item = (YamlValue, YamlValue)>,
+    {
+        let mut map = Mapping::with_capacity(capacity);
+        map.extend(iter);
+        map
+    }
+
     /// Reserves capacity for at least `additional` more elements to be inserted
     /// into the map. The map may reserve more space to avoid frequent
     /// allocations.
@@ -43,6 +68,31 @@ impl Mapping {
         self.map.reserve(additional);
     }
 
+    /// Reserves the minimum capacity for exactly `additional` more elements to
+    /// be inserted into the map. Unlike [`reserve`](Self::reserve), this does
+    /// not deliberately over-allocate, which is preferable when the final size
+    /// of the map is known. The underlying hash table may still round the
+    /// capacity up.
+    ///
+    /// `IndexMap` 1.x has no `reserve_exact`, so when the map needs to grow it
+    /// is rebuilt with the required capacity, preserving insertion order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let required = self
+            .map
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        if self.map.capacity() < required {
+            let mut map = IndexMap::with_capacity(required);
+            map.extend(self.map.drain(..));
+            self.map = map;
+        }
+    }
+
     /// Shrinks the capacity of the map as much as possible. It will drop down
     /// as much as possible while maintaining the internal rules and possibly
     /// leaving some space in accordance with the resize policy.
@@ -58,6 +108,114 @@ impl Mapping {
         self.map.insert(k, v)
     }
 
+    /// Inserts a key-value pair into the map only if the key is not already
+    /// present, returning a mutable reference to the inserted value. If the
+    /// key already existed, nothing is changed and the error holds both the
+    /// occupied entry and the value that was not inserted.
+    #[allow(clippy::result_large_err)]
+    pub fn try_insert(
+        &mut self,
+        k: YamlValue,
+        v: YamlValue,
+    ) -> Result<&mut YamlValue, OccupiedError<'_>> {
+        match self.entry(k) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value: v }),
+            Entry::Vacant(entry) => Ok(entry.insert(v)),
+        }
+    }
+
+    /// Inserts a key-value pair so that it ends up at position `index`,
+    /// shifting later entries back. If the key already existed, its value is
+    /// replaced, the entry is moved to `index` and the old value is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is past the end of the map after insertion.
+    pub fn insert_at(&mut self, index: usize, k: YamlValue, v: YamlValue) -> Option<YamlValue> {
+        let (from, old) = self.map.insert_full(k, v);
+        self.map.move_index(from, index);
+        old
+    }
+
+    /// Inserts a key-value pair directly before the entry for `anchor`. An
+    /// existing key is moved there and its old value returned, as with
+    /// [`insert_at`](Self::insert_at). If `anchor` is not in the map, nothing
+    /// is changed and the pair is handed back.
+    #[allow(clippy::result_large_err)]
+    pub fn insert_before_key(
+        &mut self,
+        anchor: &YamlValue,
+        k: YamlValue,
+        v: YamlValue,
+    ) -> Result<Option<YamlValue>, (YamlValue, YamlValue)> {
+        self.insert_next_to(anchor, k, v, false)
+    }
+
+    /// Inserts a key-value pair directly after the entry for `anchor`. An
+    /// existing key is moved there and its old value returned, as with
+    /// [`insert_at`](Self::insert_at). If `anchor` is not in the map, nothing
+    /// is changed and the pair is handed back.
+    #[allow(clippy::result_large_err)]
+    pub fn insert_after_key(
+        &mut self,
+        anchor: &YamlValue,
+        k: YamlValue,
+        v: YamlValue,
+    ) -> Result<Option<YamlValue>, (YamlValue, YamlValue)> {
+        self.insert_next_to(anchor, k, v, true)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn insert_next_to(
+        &mut self,
+        anchor: &YamlValue,
+        k: YamlValue,
+        v: YamlValue,
+        after: bool,
+    ) -> Result<Option<YamlValue>, (YamlValue, YamlValue)> {
+        let anchor = match self.map.get_index_of(anchor) {
+            Some(anchor) => anchor,
+            None => return Err((k, v)),
+        };
+        let (from, old) = self.map.insert_full(k, v);
+        // Moving `from` out shifts every later entry, the anchor included,
+        // one step forward.
+        let to = match (from.cmp(&anchor), after) {
+            (Ordering::Equal, _) => anchor,
+            (Ordering::Less, true) => anchor,
+            (Ordering::Less, false) => anchor - 1,
+            (Ordering::Greater, true) => anchor + 1,
+            (Ordering::Greater, false) => anchor,
+        };
+        self.map.move_index(from, to);
+        Ok(old)
+    }
+
+    /// Renames the key `from` to `to`, keeping the entry's value and its
+    /// position in the map. Fails without changing anything if `from` is not
+    /// present or `to` is already used by another entry, handing `to` back.
+    pub fn rename_key(&mut self, from: &YamlValue, to: YamlValue) -> Result<(), YamlValue> {
+        if from == &to && self.map.contains_key(from) {
+            return Ok(());
+        }
+        if !self.map.contains_key(from) || self.map.contains_key(&to) {
+            return Err(to);
+        }
+        let (index, _, v) = self.map.shift_remove_full(from).unwrap();
+        let (last, _) = self.map.insert_full(to, v);
+        self.map.move_index(last, index);
+        Ok(())
+    }
+
+    /// Converts this map back into a `serde_yaml::Mapping`, keeping the
+    /// entry order.
+    pub fn to_serde_yaml(&self) -> serde_yaml::Mapping {
+        self.map
+            .iter()
+            .map(|(k, v)| (serde_yaml::Value::from(k), serde_yaml::Value::from(v)))
+            .collect()
+    }
+
     /// Checks if the map contains the given key.
     #[inline]
     pub fn contains_key(&self, k: &YamlValue) -> bool {
@@ -70,6 +228,27 @@ impl Mapping {
         self.map.get(k)
     }
 
+    /// Returns the insertion index, key and value for the key in the map.
+    #[inline]
+    pub fn get_full(&self, k: &YamlValue) -> Option<(usize, &YamlValue, &YamlValue)> {
+        self.map.get_full(k)
+    }
+
+    /// Returns the key and value at the given insertion index.
+    #[inline]
+    pub fn get_index(&self, index: usize) -> Option<(&YamlValue, &YamlValue)> {
+        self.map.get_index(index)
+    }
+
+    /// Like [`get_index`](Self::get_index), but the error says which index
+    /// was out of bounds and how long the map is.
+    pub fn try_get_index(&self, index: usize) -> Result<(&YamlValue, &YamlValue), IndexError> {
+        self.map.get_index(index).ok_or(IndexError {
+            index,
+            len: self.map.len(),
+        })
+    }
+
     /// Returns the mutable reference corresponding to the key in the map.
     #[inline]
     pub fn get_mut(&mut self, k: &YamlValue) -> Option<&mut YamlValue> {
@@ -86,12 +265,39 @@ impl Mapping {
         }
     }
 
+    /// Returns a mutable reference to the value under `k`, inserting `Null`
+    /// first if the key is absent.
+    pub fn entry_or_default(&mut self, k: YamlValue) -> &mut YamlValue {
+        self.map.entry(k).or_default()
+    }
+
+    /// Returns the mapping under `k`, inserting an empty one first if the key
+    /// is absent. A value under `k` which is not a mapping, including a
+    /// tagged one, is overwritten with an empty mapping and lost.
+    pub fn get_or_insert_mapping(&mut self, k: YamlValue) -> &mut Mapping {
+        let value = self.map.entry(k).or_default();
+        if !matches!(value, YamlValue::Mapping(_)) {
+            *value = YamlValue::Mapping(Mapping::new());
+        }
+        match value {
+            YamlValue::Mapping(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
     /// Removes and returns the value corresponding to the key from the map.
     #[inline]
     pub fn remove(&mut self, k: &YamlValue) -> Option<YamlValue> {
         self.map.remove(k)
     }
 
+    /// Removes and returns the value corresponding to the key from the map,
+    /// shifting the entries after it so that the insertion order is kept.
+    #[inline]
+    pub fn shift_remove(&mut self, k: &YamlValue) -> Option<YamlValue> {
+        self.map.shift_remove(k)
+    }
+
     /// Returns the maximum number of key-value pairs the map can hold without
     /// reallocating.
     #[inline]
@@ -117,6 +323,64 @@ impl Mapping {
         self.map.clear();
     }
 
+    /// Keeps only the entries for which `keep` returns `true`, preserving the
+    /// order of the remaining entries.
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&YamlValue, &mut YamlValue) -> bool,
+    {
+        self.map.retain(keep);
+    }
+
+    /// Keeps only the entries whose key satisfies `keep`, preserving the order
+    /// of the remaining entries.
+    pub fn retain_keys<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&YamlValue) -> bool,
+    {
+        self.retain(|k, _| keep(k));
+    }
+
+    /// Keeps only the entries with a string key satisfying `keep`. Entries
+    /// whose key is not a string are removed.
+    pub fn retain_str_keys<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&str) -> bool,
+    {
+        self.retain(|k, _| k.as_str().is_some_and(&mut keep));
+    }
+
+    /// Replaces every entry with `f(key, value)`, keeping the order. Keys
+    /// cannot be mutated in place, so the map is rebuilt; when two new keys
+    /// are equal, the later entry wins, at the earlier position.
+    pub(crate) fn map_entries<F>(&mut self, mut f: F)
+    where
+        F: FnMut(YamlValue, YamlValue) -> (YamlValue, YamlValue),
+    {
+        self.map = std::mem::take(&mut self.map)
+            .into_iter()
+            .map(|(k, v)| f(k, v))
+            .collect();
+    }
+
+    /// Splits the map into the entries for which `f` returns `true` and those
+    /// for which it returns `false`, each keeping their order of insertion.
+    pub fn partition<F>(self, mut f: F) -> (Mapping, Mapping)
+    where
+        F: FnMut(&YamlValue, &YamlValue) -> bool,
+    {
+        let mut matching = Mapping::new();
+        let mut rest = Mapping::new();
+        for (k, v) in self.map {
+            if f(&k, &v) {
+                matching.insert(k, v);
+            } else {
+                rest.insert(k, v);
+            }
+        }
+        (matching, rest)
+    }
+
     /// Returns a double-ended iterator visiting all key-value pairs in order of
     /// insertion. Iterator element type is `(&'a YamlValue, &'a YamlValue)`.
     #[inline]
@@ -126,6 +390,32 @@ impl Mapping {
         }
     }
 
+    /// Returns an iterator over the entries with a `String` key, in order of
+    /// insertion, with the key borrowed as `&str`. Entries with any other
+    /// kind of key are skipped.
+    pub fn str_iter(&self) -> impl Iterator<Item = (&str, &YamlValue)> + '_ {
+        self.map.iter().filter_map(|(k, v)| Some((k.as_str()?, v)))
+    }
+
+    /// Returns an iterator over clones of the entries, in order of insertion.
+    /// Each entry is cloned only when the iterator reaches it, unlike
+    /// `map.clone().into_iter()`, which copies the whole map up front.
+    pub fn iter_cloned(&self) -> impl Iterator<Item = (YamlValue, YamlValue)> + '_ {
+        self.map.iter().map(|(k, v)| (k.clone(), v.clone()))
+    }
+
+    /// Returns an iterator over the entries sorted by key, in the same order
+    /// as [`sort_keys`](Self::sort_keys), without reordering the map itself.
+    /// Only a list of indices is allocated; keys and values are borrowed.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&YamlValue, &YamlValue)> + '_ {
+        let mut indices: Vec<usize> = (0..self.map.len()).collect();
+        let key = |index: usize| self.map.get_index(index).unwrap().0;
+        indices.sort_by(|&a, &b| total_cmp(key(a), key(b)));
+        indices
+            .into_iter()
+            .map(move |index| self.map.get_index(index).unwrap())
+    }
+
     /// Returns a double-ended iterator visiting all key-value pairs in order of
     /// insertion. Iterator element type is `(&'a YamlValue, &'a mut ValuE)`.
     #[inline]
@@ -134,6 +424,94 @@ impl Mapping {
             iter: self.map.iter_mut(),
         }
     }
+
+    /// Applies `f` to every value in order of insertion, leaving the keys and
+    /// their order untouched.
+    pub fn map_values_in_place<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut YamlValue),
+    {
+        self.map.values_mut().for_each(f);
+    }
+
+    /// Sorts the entries of the map by key, according to the ordering of
+    /// `YamlValue`. Nested mappings are left as they are; see
+    /// [`YamlValue::sort_all_keys`].
+    pub fn sort_keys(&mut self) {
+        self.map.sort_by(|a, _, b, _| total_cmp(a, b));
+    }
+
+    /// Returns the entries as `{key, value}` records, in order of insertion.
+    pub fn to_kv_list(&self) -> Vec<KeyValue> {
+        self.map
+            .iter()
+            .map(|(key, value)| KeyValue {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    /// Builds a map from `{key, value}` records, the inverse of
+    /// [`to_kv_list`](Self::to_kv_list). A repeated key keeps its first
+    /// position and its last value, as with [`insert`](Self::insert).
+    pub fn from_kv_list<I: IntoIterator<Item = KeyValue>>(list: I) -> Self {
+        list.into_iter().map(|kv| (kv.key, kv.value)).collect()
+    }
+
+    /// Consumes the map, returning its entries sorted by key in the same
+    /// order as [`sort_keys`](Self::sort_keys).
+    pub fn into_sorted_vec(self) -> Vec<(YamlValue, YamlValue)> {
+        let mut entries: Vec<_> = self.map.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| total_cmp(a, b));
+        entries
+    }
+
+    /// Returns the keys of `self` which are not in `other`, in order of
+    /// insertion.
+    pub fn keys_difference<'a>(&'a self, other: &Mapping) -> Vec<&'a YamlValue> {
+        self.map.keys().filter(|k| !other.contains_key(k)).collect()
+    }
+
+    /// Returns the keys of `self` which are also in `other`, in order of
+    /// insertion into `self`.
+    pub fn keys_intersection<'a>(&'a self, other: &Mapping) -> Vec<&'a YamlValue> {
+        self.map.keys().filter(|k| other.contains_key(k)).collect()
+    }
+
+    /// Returns the entries of `self` which are not in `other`: those whose key
+    /// is missing from `other` or maps to a different value there.
+    pub fn entries_difference<'a>(
+        &'a self,
+        other: &Mapping,
+    ) -> Vec<(&'a YamlValue, &'a YamlValue)> {
+        self.map
+            .iter()
+            .filter(|&(k, v)| other.get(k) != Some(v))
+            .collect()
+    }
+
+    /// Sums the values of the map. Returns `None` if any value is not an
+    /// integer which fits into an `i64` or if the sum overflows. An empty
+    /// map sums to `Some(0)`.
+    pub fn sum_values(&self) -> Option<i64> {
+        self.map.values().try_fold(0i64, |sum, value| match value {
+            YamlValue::Number(value) => sum.checked_add(value.as_i64()?),
+            _ => None,
+        })
+    }
+
+    /// Returns the greatest value of the map according to the ordering of
+    /// `YamlValue`, or `None` if the map is empty.
+    pub fn max_value(&self) -> Option<&YamlValue> {
+        self.map.values().max_by(|a, b| total_cmp(a, b))
+    }
+
+    /// Returns the least value of the map according to the ordering of
+    /// `YamlValue`, or `None` if the map is empty.
+    pub fn min_value(&self) -> Option<&YamlValue> {
+        self.map.values().min_by(|a, b| total_cmp(a, b))
+    }
 }
 
 impl From<serde_yaml::Mapping> for Mapping {
@@ -161,74 +539,84 @@ impl Hash for Mapping {
     }
 }
 
-impl PartialOrd for Mapping {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let mut self_entries = Vec::from_iter(self);
-        let mut other_entries = Vec::from_iter(other);
+/// Sorts in an arbitrary order that is consistent with YamlValue's PartialOrd
+/// impl.
+pub(crate) fn total_cmp(a: &YamlValue, b: &YamlValue) -> Ordering {
+    match (a, b) {
+        (YamlValue::Null, YamlValue::Null) => Ordering::Equal,
+        (YamlValue::Null, _) => Ordering::Less,
+        (_, YamlValue::Null) => Ordering::Greater,
+
+        (YamlValue::Bool(a), YamlValue::Bool(b)) => a.cmp(b),
+        (YamlValue::Bool(_), _) => Ordering::Less,
+        (_, YamlValue::Bool(_)) => Ordering::Greater,
+
+        (YamlValue::Number(a), YamlValue::Number(b)) => a.cmp(b),
+        (YamlValue::Number(_), _) => Ordering::Less,
+        (_, YamlValue::Number(_)) => Ordering::Greater,
+
+        (YamlValue::String(a), YamlValue::String(b)) => a.cmp(b),
+        (YamlValue::String(_), _) => Ordering::Less,
+        (_, YamlValue::String(_)) => Ordering::Greater,
+
+        (YamlValue::Bytes(a), YamlValue::Bytes(b)) => a.cmp(b),
+        (YamlValue::Bytes(_), _) => Ordering::Less,
+        (_, YamlValue::Bytes(_)) => Ordering::Greater,
+
+        (YamlValue::Sequence(a), YamlValue::Sequence(b)) => iter_cmp_by(a, b, total_cmp),
+        (YamlValue::Sequence(_), _) => Ordering::Less,
+        (_, YamlValue::Sequence(_)) => Ordering::Greater,
+
+        (YamlValue::Mapping(a), YamlValue::Mapping(b)) => {
+            iter_cmp_by(a, b, |(ak, av), (bk, bv)| {
+                total_cmp(ak, bk).then_with(|| total_cmp(av, bv))
+            })
+        }
+        (YamlValue::Mapping(_), _) => Ordering::Less,
+        (_, YamlValue::Mapping(_)) => Ordering::Greater,
 
-        // Sort in an arbitrary order that is consistent with YamlValue's PartialOrd
-        // impl.
-        fn total_cmp(a: &YamlValue, b: &YamlValue) -> Ordering {
-            match (a, b) {
-                (YamlValue::Null, YamlValue::Null) => Ordering::Equal,
-                (YamlValue::Null, _) => Ordering::Less,
-                (_, YamlValue::Null) => Ordering::Greater,
-
-                (YamlValue::Bool(a), YamlValue::Bool(b)) => a.cmp(b),
-                (YamlValue::Bool(_), _) => Ordering::Less,
-                (_, YamlValue::Bool(_)) => Ordering::Greater,
-
-                (YamlValue::Number(a), YamlValue::Number(b)) => a.cmp(b),
-                (YamlValue::Number(_), _) => Ordering::Less,
-                (_, YamlValue::Number(_)) => Ordering::Greater,
-
-                (YamlValue::String(a), YamlValue::String(b)) => a.cmp(b),
-                (YamlValue::String(_), _) => Ordering::Less,
-                (_, YamlValue::String(_)) => Ordering::Greater,
-
-                (YamlValue::Sequence(a), YamlValue::Sequence(b)) => iter_cmp_by(a, b, total_cmp),
-                (YamlValue::Sequence(_), _) => Ordering::Less,
-                (_, YamlValue::Sequence(_)) => Ordering::Greater,
-
-                (YamlValue::Mapping(a), YamlValue::Mapping(b)) => {
-                    iter_cmp_by(a, b, |(ak, av), (bk, bv)| {
-                        total_cmp(ak, bk).then_with(|| total_cmp(av, bv))
-                    })
-                }
-            }
+        (YamlValue::Tagged { tag: a, value: av }, YamlValue::Tagged { tag: b, value: bv }) => {
+            a.cmp(b).then_with(|| total_cmp(av, bv))
         }
+    }
+}
 
-        fn iter_cmp_by<I, F>(this: I, other: I, mut cmp: F) -> Ordering
-            where
-                I: IntoIterator,
-                F: FnMut(I::Item, I::Item) -> Ordering,
-        {
-            let mut this = this.into_iter();
-            let mut other = other.into_iter();
-
-            loop {
-                let x = match this.next() {
-                    None => {
-                        if other.next().is_none() {
-                            return Ordering::Equal;
-                        } else {
-                            return Ordering::Less;
-                        }
-                    }
-                    Some(val) => val,
-                };
-
-                let y = match other.next() {
-                    None => return Ordering::Greater,
-                    Some(val) => val,
-                };
-
-                match cmp(x, y) {
-                    Ordering::Equal => {}
-                    non_eq => return non_eq,
+fn iter_cmp_by<I, F>(this: I, other: I, mut cmp: F) -> Ordering
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item, I::Item) -> Ordering,
+{
+    let mut this = this.into_iter();
+    let mut other = other.into_iter();
+
+    loop {
+        let x = match this.next() {
+            None => {
+                if other.next().is_none() {
+                    return Ordering::Equal;
+                } else {
+                    return Ordering::Less;
                 }
             }
+            Some(val) => val,
+        };
+
+        let y = match other.next() {
+            None => return Ordering::Greater,
+            Some(val) => val,
+        };
+
+        match cmp(x, y) {
+            Ordering::Equal => {}
+            non_eq => return non_eq,
         }
+    }
+}
+
+impl PartialOrd for Mapping {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let mut self_entries = Vec::from_iter(self);
+        let mut other_entries = Vec::from_iter(other);
 
         // While sorting by map key, we get to assume that no two keys are
         // equal, otherwise they wouldn't both be in the map. This is not a safe
@@ -262,6 +650,14 @@ impl Extend<(YamlValue, YamlValue)> for Mapping {
     }
 }
 
+impl<const N: usize> From<[(YamlValue, YamlValue); N]> for Mapping {
+    /// Builds a map from an array of pairs. As with `insert`, a later
+    /// duplicate key overwrites the earlier value but keeps its position.
+    fn from(pairs: [(YamlValue, YamlValue); N]) -> Self {
+        Mapping::from_iter(pairs)
+    }
+}
+
 impl FromIterator<(YamlValue, YamlValue)> for Mapping {
     #[inline]
     fn from_iter<I: IntoIterator<Item = (YamlValue, YamlValue)>>(iter: I) -> Self {
@@ -470,6 +866,60 @@ impl<'a> VacantEntry<'a> {
     }
 }
 
+/// The error returned by [`Mapping::try_insert`] when the key already exists.
+pub struct OccupiedError<'a> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: YamlValue,
+}
+
+impl<'a> fmt::Debug for OccupiedError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<'a> fmt::Display for OccupiedError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+impl<'a> std::error::Error for OccupiedError<'a> {}
+
+/// The error returned by [`Mapping::try_get_index`] when the index is out of
+/// bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexError {
+    /// The index which was asked for.
+    pub index: usize,
+    /// The number of entries in the map.
+    pub len: usize,
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "index {} is out of bounds for a mapping with {} entries",
+            self.index, self.len
+        )
+    }
+}
+
+impl std::error::Error for IndexError {}
+
 impl Serialize for Mapping {
     #[inline]
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
@@ -482,41 +932,509 @@ impl Serialize for Mapping {
     }
 }
 
+/// Serializes a [`Mapping`] with its keys in sorted order, without modifying
+/// the mapping itself. Mappings nested anywhere inside the values are sorted
+/// as well.
+///
+/// Keys are ordered consistently with `YamlValue`'s `PartialOrd` impl.
+#[derive(Clone, Copy, Debug)]
+pub struct SortedMapping<'a>(pub &'a Mapping);
+
+impl<'a> Serialize for SortedMapping<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut entries = Vec::from_iter(self.0);
+        entries.sort_by(|&(a, _), &(b, _)| total_cmp(a, b));
+        let mut map_serializer = serializer.serialize_map(Some(entries.len()))?;
+        for (k, v) in entries {
+            map_serializer.serialize_entry(&SortedValue(k), &SortedValue(v))?;
+        }
+        map_serializer.end()
+    }
+}
+
+struct SortedValue<'a>(&'a YamlValue);
+
+impl<'a> Serialize for SortedValue<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            YamlValue::Mapping(map) => SortedMapping(map).serialize(serializer),
+            YamlValue::Sequence(seq) => serializer.collect_seq(seq.iter().map(SortedValue)),
+            value => value.serialize(serializer),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Mapping {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: Deserializer<'de>,
     {
-        struct Visitor;
+        deserializer.deserialize_map(MappingVisitor {
+            remaining_depth: MAX_DEPTH - 1,
+        })
+    }
+}
 
-        impl<'de> serde::de::Visitor<'de> for Visitor {
-            type Value = Mapping;
+/// Deserializes a mapping whose keys and values may nest at most
+/// `remaining_depth` further levels of sequences or mappings.
+pub(crate) struct MappingVisitor {
+    pub(crate) remaining_depth: usize,
+}
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a YAML mapping")
-            }
+impl<'de> serde::de::Visitor<'de> for MappingVisitor {
+    type Value = Mapping;
 
-            #[inline]
-            fn visit_unit<E>(self) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-            {
-                Ok(Mapping::new())
-            }
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a YAML mapping")
+    }
 
-            #[inline]
-            fn visit_map<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
-                where
-                    V: serde::de::MapAccess<'de>,
-            {
-                let mut values = Mapping::new();
-                while let Some((k, v)) = visitor.next_entry()? {
-                    values.insert(k, v);
-                }
-                Ok(values)
-            }
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+    {
+        Ok(Mapping::new())
+    }
+
+    #[inline]
+    fn visit_map<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+        where
+            V: serde::de::MapAccess<'de>,
+    {
+        let seed = ValueVisitor {
+            remaining_depth: self.remaining_depth,
+        };
+        let mut values = Mapping::with_capacity(visitor.size_hint().unwrap_or(0));
+        while let Some((k, v)) = visitor.next_entry_seed(seed, seed)? {
+            values.insert(k, v);
         }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted_mapping_serialization() {
+        let map: Mapping = serde_yaml::from_str("b: 1\na: {d: 2, c: 3}\n").unwrap();
+        let sorted = serde_yaml::to_string(&SortedMapping(&map)).unwrap();
+        assert_eq!(sorted, "---\na:\n  c: 3\n  d: 2\nb: 1\n");
+
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, vec![YamlValue::from("b"), YamlValue::from("a")]);
+    }
+
+    #[test]
+    fn reserve_exact_grows_capacity() {
+        let mut map = Mapping::new();
+        map.insert(YamlValue::from("b"), YamlValue::from(1));
+        map.insert(YamlValue::from("a"), YamlValue::from(2));
+        map.reserve_exact(20);
+        assert!(map.capacity() >= 22);
+
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, vec![YamlValue::from("b"), YamlValue::from("a")]);
+    }
+
+    #[test]
+    fn deserialize_preserves_key_order() {
+        let map: Mapping = serde_yaml::from_str("{z: 1, a: 2, m: 3}").unwrap();
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(
+            keys,
+            vec![YamlValue::from("z"), YamlValue::from("a"), YamlValue::from("m")]
+        );
+
+        let value: YamlValue = serde_yaml::from_str("{z: 1, a: 2, m: 3}").unwrap();
+        let YamlValue::Mapping(map) = value else {
+            panic!("expected a mapping")
+        };
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(
+            keys,
+            vec![YamlValue::from("z"), YamlValue::from("a"), YamlValue::from("m")]
+        );
+    }
+
+    #[test]
+    fn try_insert_into_empty() {
+        let mut map = Mapping::new();
+        let value = map.try_insert(YamlValue::from("a"), YamlValue::from(1)).unwrap();
+        *value = YamlValue::from(2);
+        assert_eq!(map.get(&YamlValue::from("a")), Some(&YamlValue::from(2)));
+    }
+
+    #[test]
+    fn try_insert_conflict_on_existing() {
+        let mut map = Mapping::new();
+        map.insert(YamlValue::from("a"), YamlValue::from(1));
+        let err = map
+            .try_insert(YamlValue::from("a"), YamlValue::from(2))
+            .unwrap_err();
+        assert_eq!(err.entry.key(), &YamlValue::from("a"));
+        assert_eq!(err.entry.get(), &YamlValue::from(1));
+        assert_eq!(err.value, YamlValue::from(2));
+        assert_eq!(map.get(&YamlValue::from("a")), Some(&YamlValue::from(1)));
+    }
+
+    #[test]
+    fn positional_insert() {
+        let keys =
+            |map: &Mapping| -> Vec<YamlValue> { map.iter().map(|(k, _)| k.clone()).collect() };
+        let mut map: Mapping = vec![("id", 1), ("name", 2), ("end", 3)]
+            .into_iter()
+            .map(|(k, v)| (YamlValue::from(k), YamlValue::from(v)))
+            .collect();
+
+        assert_eq!(
+            map.insert_after_key(
+                &YamlValue::from("id"),
+                YamlValue::from("hash"),
+                YamlValue::from(4)
+            ),
+            Ok(None)
+        );
+        assert_eq!(
+            map.insert_at(0, YamlValue::from("first"), YamlValue::from(5)),
+            None
+        );
+        assert_eq!(
+            map.insert_before_key(
+                &YamlValue::from("id"),
+                YamlValue::from("end"),
+                YamlValue::from(6)
+            ),
+            Ok(Some(YamlValue::from(3)))
+        );
+        assert_eq!(
+            keys(&map),
+            vec!["first", "end", "id", "hash", "name"]
+                .into_iter()
+                .map(YamlValue::from)
+                .collect::<Vec<_>>()
+        );
+        assert!(map
+            .insert_after_key(
+                &YamlValue::from("missing"),
+                YamlValue::from("x"),
+                YamlValue::Null
+            )
+            .is_err());
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn get_full() {
+        let mut map = Mapping::new();
+        map.insert(YamlValue::from("a"), YamlValue::from(1));
+        map.insert(YamlValue::from("b"), YamlValue::from(2));
+
+        assert_eq!(
+            map.get_full(&YamlValue::from("b")),
+            Some((1, &YamlValue::from("b"), &YamlValue::from(2)))
+        );
+        assert_eq!(map.get_full(&YamlValue::from("c")), None);
+    }
+
+    #[test]
+    fn serde_yaml_round_trip() {
+        let original: serde_yaml::Mapping =
+            serde_yaml::from_str("{b: 1, a: [x, {c: true}], 3: null}").unwrap();
+        let mapping = Mapping::from(original.clone());
+        assert_eq!(mapping.to_serde_yaml(), original);
+    }
+
+    #[test]
+    fn numeric_aggregates() {
+        let numbers: Mapping = vec![("a", 3), ("b", -1), ("c", 7)]
+            .into_iter()
+            .map(|(k, v)| (YamlValue::from(k), YamlValue::from(v)))
+            .collect();
+        assert_eq!(numbers.sum_values(), Some(9));
+        assert_eq!(numbers.max_value(), Some(&YamlValue::from(7)));
+        assert_eq!(numbers.min_value(), Some(&YamlValue::from(-1)));
+
+        let mut mixed = numbers.clone();
+        mixed.insert(YamlValue::from("d"), YamlValue::from("x"));
+        assert_eq!(mixed.sum_values(), None);
+        assert_eq!(mixed.max_value(), Some(&YamlValue::from("x")));
+        assert_eq!(mixed.min_value(), Some(&YamlValue::from(-1)));
+
+        assert_eq!(Mapping::new().sum_values(), Some(0));
+        assert_eq!(Mapping::new().max_value(), None);
+    }
+
+    #[test]
+    fn sort_keys() {
+        let mut map: Mapping = vec![("b", 1), ("c", 2), ("a", 3)]
+            .into_iter()
+            .map(|(k, v)| (YamlValue::from(k), YamlValue::from(v)))
+            .collect();
+        map.sort_keys();
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, vec![YamlValue::from("a"), YamlValue::from("b"), YamlValue::from("c")]);
+    }
+
+    #[test]
+    fn rename_key() {
+        let mut map = Mapping::from_iter(vec![
+            (YamlValue::from("a"), YamlValue::from(1)),
+            (YamlValue::from("b"), YamlValue::from(2)),
+            (YamlValue::from("c"), YamlValue::from(3)),
+        ]);
+        assert_eq!(map.rename_key(&YamlValue::from("b"), YamlValue::from("x")), Ok(()));
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, vec![YamlValue::from("a"), YamlValue::from("x"), YamlValue::from("c")]);
+        assert_eq!(map.get(&YamlValue::from("x")), Some(&YamlValue::from(2)));
+
+        let missing = map.rename_key(&YamlValue::from("b"), YamlValue::from("y"));
+        assert_eq!(missing, Err(YamlValue::from("y")));
+        let taken = map.rename_key(&YamlValue::from("a"), YamlValue::from("c"));
+        assert_eq!(taken, Err(YamlValue::from("c")));
+        assert_eq!(map.get(&YamlValue::from("a")), Some(&YamlValue::from(1)));
+    }
+
+    #[test]
+    fn entry_or_default() {
+        let mut map = Mapping::new();
+        let slot = map.entry_or_default(YamlValue::from("servers"));
+        assert_eq!(*slot, YamlValue::Null);
+        slot.push(YamlValue::from("a")).unwrap();
+        map.entry_or_default(YamlValue::from("servers"))
+            .push(YamlValue::from("b"))
+            .unwrap();
+        assert_eq!(
+            map.get(&YamlValue::from("servers")),
+            Some(&crate::yaml!(["a", "b"]))
+        );
+    }
+
+    #[test]
+    fn iter_sorted() {
+        let map: Mapping = serde_yaml::from_str("b: 1\n2: x\na: 3\nnull: y\n").unwrap();
+        let keys: Vec<_> = map.iter_sorted().map(|(k, _)| k.clone()).collect();
+        assert_eq!(
+            keys,
+            [
+                YamlValue::Null,
+                YamlValue::from(2),
+                YamlValue::from("a"),
+                YamlValue::from("b")
+            ]
+        );
+        let (_, value) = map.iter_sorted().nth(2).unwrap();
+        assert_eq!(value, &YamlValue::from(3));
+
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(
+            keys,
+            [
+                YamlValue::from("b"),
+                YamlValue::from(2),
+                YamlValue::from("a"),
+                YamlValue::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn try_get_index() {
+        let map: Mapping = serde_yaml::from_str("a: 1\nb: 2\n").unwrap();
+        assert_eq!(
+            map.try_get_index(1),
+            Ok((&YamlValue::from("b"), &YamlValue::from(2)))
+        );
+        let err = map.try_get_index(2).unwrap_err();
+        assert_eq!(err, IndexError { index: 2, len: 2 });
+        assert_eq!(
+            err.to_string(),
+            "index 2 is out of bounds for a mapping with 2 entries"
+        );
+        assert_eq!(map.get_index(2), None);
+    }
+
+    #[test]
+    fn str_iter() {
+        let map: Mapping = serde_yaml::from_str("a: 1
+2: skipped
+b: 3
+").unwrap();
+        let entries: Vec<_> = map.str_iter().collect();
+        assert_eq!(
+            entries,
+            vec![("a", &YamlValue::from(1)), ("b", &YamlValue::from(3))]
+        );
+    }
+
+    #[test]
+    fn from_iter_with_capacity() {
+        let pairs = || (0..100).map(|i| (YamlValue::from(i % 50), YamlValue::from(i)));
+        let map = Mapping::from_iter_with_capacity(pairs(), 100);
+        assert_eq!(map, Mapping::from_iter(pairs()));
+        assert_eq!(map.len(), 50);
+        assert!(map.capacity() >= 100);
+    }
+
+    #[test]
+    fn from_pairs() {
+        let map = Mapping::from_pairs([
+            (YamlValue::from("name"), YamlValue::from("app")),
+            (YamlValue::from("port"), YamlValue::from(8080)),
+            (YamlValue::from("debug"), YamlValue::from(false)),
+        ]);
+        assert_eq!(map.len(), 3);
+        let keys: Vec<_> = map.str_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["name", "port", "debug"]);
+        assert_eq!(map.get(&YamlValue::from("port")), Some(&YamlValue::from(8080)));
+
+        let from = Mapping::from([
+            (YamlValue::from("name"), YamlValue::from("app")),
+            (YamlValue::from("port"), YamlValue::from(8080)),
+            (YamlValue::from("debug"), YamlValue::from(false)),
+        ]);
+        assert_eq!(from, map);
+    }
+
+    #[test]
+    fn partition() {
+        let map: Mapping = ["x-a", "name", "x-b", "port"]
+            .iter()
+            .map(|k| (YamlValue::from(*k), YamlValue::from(k.len() as u64)))
+            .collect();
+        let (extensions, known) = map.partition(|k, _| k.as_str().unwrap().starts_with("x-"));
+        let keys = |map: &Mapping| map.str_iter().map(|(k, _)| k.to_owned()).collect::<Vec<_>>();
+        assert_eq!(keys(&extensions), ["x-a", "x-b"]);
+        assert_eq!(keys(&known), ["name", "port"]);
+        assert_eq!(known[&YamlValue::from("port")], YamlValue::from(4));
+    }
+
+    #[test]
+    fn map_values_in_place() {
+        let mut map: Mapping = (1..=3)
+            .map(|i| (YamlValue::from(format!("k{}", 4 - i)), YamlValue::from(i)))
+            .collect();
+        map.map_values_in_place(|v| *v = -v.take());
+        let entries: Vec<_> = map
+            .str_iter()
+            .map(|(k, v)| (k, v.as_i64_strict().unwrap()))
+            .collect();
+        assert_eq!(entries, [("k3", -1), ("k2", -2), ("k1", -3)]);
+    }
+
+    #[test]
+    fn into_sorted_vec() {
+        let map: Mapping = serde_yaml::from_str("b: 1\n2: x\na: 3\nnull: y\n").unwrap();
+        assert_eq!(
+            map.into_sorted_vec(),
+            [
+                (YamlValue::Null, YamlValue::from("y")),
+                (YamlValue::from(2), YamlValue::from("x")),
+                (YamlValue::from("a"), YamlValue::from(3)),
+                (YamlValue::from("b"), YamlValue::from(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_set_operations() {
+        let ours: Mapping = serde_yaml::from_str("a: 1\nb: 2\nc: 3\n").unwrap();
+        let theirs: Mapping = serde_yaml::from_str("d: 4\nc: 3\nb: 20\n").unwrap();
+        let key = |k: &str| YamlValue::from(k);
+
+        assert_eq!(ours.keys_difference(&theirs), [&key("a")]);
+        assert_eq!(ours.keys_intersection(&theirs), [&key("b"), &key("c")]);
+        assert_eq!(theirs.keys_intersection(&ours), [&key("c"), &key("b")]);
+        assert_eq!(
+            ours.entries_difference(&theirs),
+            [
+                (&key("a"), &YamlValue::from(1)),
+                (&key("b"), &YamlValue::from(2))
+            ]
+        );
+
+        let disjoint: Mapping = serde_yaml::from_str("x: 1\n").unwrap();
+        assert_eq!(ours.keys_difference(&disjoint).len(), 3);
+        assert!(ours.keys_intersection(&disjoint).is_empty());
+        assert_eq!(ours.entries_difference(&disjoint).len(), 3);
+        assert!(ours.entries_difference(&ours).is_empty());
+    }
+
+    #[test]
+    fn kv_list_round_trip() {
+        let map: Mapping = serde_yaml::from_str("b: 1\na: [x]\n3: null\n").unwrap();
+        let list = map.to_kv_list();
+        assert_eq!(
+            list[0],
+            KeyValue {
+                key: YamlValue::from("b"),
+                value: YamlValue::from(1)
+            }
+        );
+        assert_eq!(
+            serde_yaml::to_string(&list[1..]).unwrap(),
+            "---\n- key: a\n  value:\n    - x\n- key: 3\n  value: ~\n"
+        );
+
+        let round_trip = Mapping::from_kv_list(list);
+        assert_eq!(round_trip, map);
+        let keys: Vec<_> = round_trip.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, [YamlValue::from("b"), YamlValue::from("a"), YamlValue::from(3)]);
+    }
+
+    #[test]
+    fn retain_keys() {
+        let mut map = Mapping::new();
+        map.insert("name".into(), 1.into());
+        map.insert("debug".into(), 2.into());
+        map.insert(3.into(), 3.into());
+        map.insert("port".into(), 4.into());
+
+        let mut by_value = map.clone();
+        by_value.retain_keys(|k| k.as_str().is_none());
+        assert_eq!(by_value.len(), 1);
+        assert!(by_value.contains_key(&YamlValue::from(3)));
+
+        let allowed = ["port", "name"];
+        map.retain_str_keys(|k| allowed.contains(&k));
+        let keys: Vec<_> = map.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, [&YamlValue::from("name"), &YamlValue::from("port")]);
+    }
+
+    #[test]
+    fn iter_cloned() {
+        let mut map = Mapping::new();
+        map.insert("a".into(), YamlValue::Sequence(vec![1.into(), 2.into()]));
+        map.insert("b".into(), "x".into());
+        let original = map.clone();
+
+        let mut cloned: Vec<_> = map.iter_cloned().collect();
+        cloned[0].1.push(3.into()).unwrap();
+        assert_eq!(map, original);
+        assert_eq!(cloned[1], (YamlValue::from("b"), YamlValue::from("x")));
+        assert_eq!(map.iter_cloned().collect::<Mapping>(), map);
+    }
 
-        deserializer.deserialize_map(Visitor)
+    #[test]
+    fn get_or_insert_mapping() {
+        let mut map = Mapping::new();
+        map.get_or_insert_mapping("server".into())
+            .insert("port".into(), 80.into());
+        map.get_or_insert_mapping("server".into())
+            .insert("host".into(), "a".into());
+        map.insert("name".into(), "app".into());
+        map.get_or_insert_mapping("name".into())
+            .insert("first".into(), "b".into());
+
+        let value = YamlValue::Mapping(map);
+        assert_eq!(
+            value,
+            crate::yaml!({
+                "server": {"port": 80, "host": "a"},
+                "name": {"first": "b"}
+            })
+        );
     }
 }
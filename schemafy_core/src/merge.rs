@@ -0,0 +1,116 @@
+//! Recursive merging of `YamlValue`s for layering configuration.
+
+use crate::mapping::{Entry, Mapping};
+use crate::yaml_value::YamlValue;
+
+/// How [`YamlValue::merge`] combines two sequences. Mappings are always
+/// merged key by key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The incoming sequence replaces the existing one.
+    ReplaceSeq,
+    /// The incoming elements are appended to the existing sequence.
+    ConcatSeq,
+    /// Elements at the same index are merged; extra incoming elements are
+    /// appended.
+    MergeSeqByIndex,
+}
+
+impl YamlValue {
+    /// Merges `other` into `self`. Mappings are merged recursively, keeping
+    /// the order of existing keys and appending new ones; sequences are
+    /// combined according to `strategy`. In every other case, including a
+    /// type mismatch, `other` replaces `self`.
+    pub fn merge(&mut self, other: YamlValue, strategy: MergeStrategy) {
+        match (self, other) {
+            (YamlValue::Mapping(lhs), YamlValue::Mapping(rhs)) => lhs.merge(rhs, strategy),
+            (YamlValue::Sequence(lhs), YamlValue::Sequence(rhs)) => match strategy {
+                MergeStrategy::ReplaceSeq => *lhs = rhs,
+                MergeStrategy::ConcatSeq => lhs.extend(rhs),
+                MergeStrategy::MergeSeqByIndex => {
+                    let mut rhs = rhs.into_iter();
+                    for (lhs, rhs) in lhs.iter_mut().zip(&mut rhs) {
+                        lhs.merge(rhs, strategy);
+                    }
+                    lhs.extend(rhs);
+                }
+            },
+            (lhs, rhs) => *lhs = rhs,
+        }
+    }
+}
+
+impl Mapping {
+    /// Merges the entries of `other` into this map, merging values present in
+    /// both with [`YamlValue::merge`].
+    pub fn merge(&mut self, other: Mapping, strategy: MergeStrategy) {
+        for (k, v) in other {
+            match self.entry(k) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(v, strategy),
+                Entry::Vacant(entry) => {
+                    entry.insert(v);
+                }
+            }
+        }
+    }
+
+    /// Merges `other` into this map, replacing sequences wholesale.
+    pub fn deep_merge(&mut self, other: Mapping) {
+        self.merge(other, MergeStrategy::ReplaceSeq);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml;
+
+    fn merged(strategy: MergeStrategy) -> YamlValue {
+        let mut base =
+            yaml!({"name": "a", "ports": [{"port": 80}, {"port": 443}], "tls": {"on": false}});
+        let overlay = yaml!({"ports": [{"host": "x"}], "tls": {"on": true}, "debug": true});
+        base.merge(overlay, strategy);
+        base
+    }
+
+    #[test]
+    fn replace_seq() {
+        assert_eq!(
+            merged(MergeStrategy::ReplaceSeq),
+            yaml!({"name": "a", "ports": [{"host": "x"}], "tls": {"on": true}, "debug": true})
+        );
+    }
+
+    #[test]
+    fn concat_seq() {
+        assert_eq!(
+            merged(MergeStrategy::ConcatSeq),
+            yaml!({
+                "name": "a",
+                "ports": [{"port": 80}, {"port": 443}, {"host": "x"}],
+                "tls": {"on": true},
+                "debug": true
+            })
+        );
+    }
+
+    #[test]
+    fn merge_seq_by_index() {
+        assert_eq!(
+            merged(MergeStrategy::MergeSeqByIndex),
+            yaml!({
+                "name": "a",
+                "ports": [{"port": 80, "host": "x"}, {"port": 443}],
+                "tls": {"on": true},
+                "debug": true
+            })
+        );
+    }
+
+    #[test]
+    fn mismatched_types_are_replaced() {
+        let mut value = yaml!({"a": [1]});
+        value.merge(yaml!({"a": {"b": 1}}), MergeStrategy::ConcatSeq);
+        assert_eq!(value, yaml!({"a": {"b": 1}}));
+    }
+}
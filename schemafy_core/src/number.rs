@@ -0,0 +1,425 @@
+//! The number type held by `YamlValue::Number`.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A YAML number which remembers whether it was written as an integer or a
+/// float, so that it serializes back in the same form.
+///
+/// Integers are stored by sign rather than by how they were produced, so
+/// `YamlNumber::from(3u64) == YamlNumber::from(3i64)`. An integer and a float
+/// are never equal, even when they have the same value; use
+/// [`YamlValue::numeric_eq`](crate::yaml_value::YamlValue::numeric_eq) for
+/// that.
+#[derive(Clone, Copy)]
+pub struct YamlNumber {
+    n: N,
+}
+
+#[derive(Clone, Copy)]
+enum N {
+    /// Always greater than or equal to zero.
+    PosInt(u64),
+    /// Always less than zero.
+    NegInt(i64),
+    Float(f64),
+}
+
+impl YamlNumber {
+    /// Returns `true` if this is an integer which fits into an `i64`.
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Returns `true` if this is a non-negative integer.
+    pub fn is_u64(&self) -> bool {
+        matches!(self.n, N::PosInt(_))
+    }
+
+    /// Returns `true` if this was written as a float.
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(_))
+    }
+
+    /// Returns the value if this is an integer which fits into an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(value) => i64::try_from(value).ok(),
+            N::NegInt(value) => Some(value),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Returns the value if this is a non-negative integer.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float. Integers are converted, which loses
+    /// precision above 2^53.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.n {
+            N::PosInt(value) => Some(value as f64),
+            N::NegInt(value) => Some(value as f64),
+            N::Float(value) => Some(value),
+        }
+    }
+
+    /// Returns the value as an integer if it is one, or a float with no
+    /// fractional part which fits into an `i64`.
+    pub(crate) fn to_i64_exact(self) -> Option<i64> {
+        match self.n {
+            N::Float(value)
+                if value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64 =>
+            {
+                Some(value as i64)
+            }
+            _ => self.as_i64(),
+        }
+    }
+
+    /// Returns `true` if the number is zero.
+    pub(crate) fn is_zero(&self) -> bool {
+        match self.n {
+            N::PosInt(value) => value == 0,
+            N::NegInt(_) => false,
+            N::Float(value) => value == 0.0,
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self.n {
+            N::PosInt(value) => Some(value.into()),
+            N::NegInt(value) => Some(value.into()),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Applies an integer operation in `i128`, saturating at the bounds of
+    /// `i64`, or at `i64::MIN` and `u64::MAX` when an operand is above
+    /// `i64::MAX`. If either operand is a float, `float_op` is used instead.
+    fn combine(
+        self,
+        rhs: Self,
+        int_op: fn(i128, i128) -> i128,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Self {
+        let (Some(a), Some(b)) = (self.as_i128(), rhs.as_i128()) else {
+            return YamlNumber::from(float_op(self.as_f64().unwrap(), rhs.as_f64().unwrap()));
+        };
+        let max = if self.is_i64() && rhs.is_i64() {
+            i64::MAX.into()
+        } else {
+            u64::MAX.into()
+        };
+        let value = int_op(a, b).clamp(i64::MIN.into(), max);
+        match u64::try_from(value) {
+            Ok(value) => YamlNumber::from(value),
+            Err(_) => YamlNumber::from(value as i64),
+        }
+    }
+
+    /// Adds two numbers. See [`YamlValue`](crate::yaml_value::YamlValue)'s
+    /// `AddAssign` impl for how overflow is handled.
+    pub(crate) fn saturating_add(self, rhs: Self) -> Self {
+        self.combine(rhs, |a, b| a + b, |a, b| a + b)
+    }
+
+    /// Subtracts two numbers, saturating like
+    /// [`saturating_add`](Self::saturating_add).
+    pub(crate) fn saturating_sub(self, rhs: Self) -> Self {
+        self.combine(rhs, |a, b| a - b, |a, b| a - b)
+    }
+
+    /// Negates the number, saturating at `i64::MIN`/`i64::MAX`.
+    pub(crate) fn saturating_neg(self) -> Self {
+        match self.n {
+            N::Float(value) => YamlNumber::from(-value),
+            _ => {
+                let value = -self.as_i128().unwrap();
+                YamlNumber::from(value.clamp(i64::MIN.into(), i64::MAX.into()) as i64)
+            }
+        }
+    }
+
+    /// The rank of the representation, used to order an integer and a float
+    /// with the same value.
+    fn kind(&self) -> u8 {
+        match self.n {
+            N::PosInt(_) | N::NegInt(_) => 0,
+            N::Float(_) => 1,
+        }
+    }
+
+    /// Feeds a representation of the number to `write` which is the same
+    /// for equal numbers. Integral floats which fit into an `i64` are
+    /// written like the integer when `normalize` is set.
+    pub(crate) fn write_canonical(&self, normalize: bool, write: &mut dyn FnMut(&[u8])) {
+        let int = if normalize {
+            self.to_i64_exact()
+        } else {
+            self.as_i64()
+        };
+        match (int, self.n) {
+            (Some(value), _) => {
+                write(&[0]);
+                write(&value.to_le_bytes());
+            }
+            (None, N::PosInt(value)) => {
+                write(&[1]);
+                write(&value.to_le_bytes());
+            }
+            (None, N::Float(value)) => {
+                write(&[2]);
+                write(&canonical_bits(value).to_le_bytes());
+            }
+            (None, N::NegInt(_)) => unreachable!("negative integers always fit into an i64"),
+        }
+    }
+}
+
+/// Returns the bits of `value`, with `-0.0` mapped to `0.0` and every NaN to
+/// the same NaN, matching the `PartialEq` impl.
+fn canonical_bits(value: f64) -> u64 {
+    if value == 0.0 {
+        0.0f64.to_bits()
+    } else if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+impl PartialEq for YamlNumber {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.n, other.n) {
+            (N::PosInt(a), N::PosInt(b)) => a == b,
+            (N::NegInt(a), N::NegInt(b)) => a == b,
+            // NaN equals itself so that `YamlValue` can be `Eq` and used as
+            // a mapping key.
+            (N::Float(a), N::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for YamlNumber {}
+
+impl Hash for YamlNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.n {
+            N::PosInt(value) => (0, value).hash(state),
+            N::NegInt(value) => (1, value).hash(state),
+            N::Float(value) => (2, canonical_bits(value)).hash(state),
+        }
+    }
+}
+
+impl YamlNumber {
+    /// Compares by mathematical value only, with NaN above everything else.
+    /// Unlike `cmp`, `2` and `2.0` are equal.
+    pub(crate) fn cmp_value(&self, other: &Self) -> Ordering {
+        match (self.n, other.n) {
+            (N::PosInt(a), N::PosInt(b)) => a.cmp(&b),
+            (N::NegInt(a), N::NegInt(b)) => a.cmp(&b),
+            (N::NegInt(_), N::PosInt(_)) => Ordering::Less,
+            (N::PosInt(_), N::NegInt(_)) => Ordering::Greater,
+            _ => {
+                let (a, b) = (self.as_f64().unwrap(), other.as_f64().unwrap());
+                match (a.is_nan(), b.is_nan()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Greater,
+                    (false, true) => Ordering::Less,
+                    (false, false) => a.partial_cmp(&b).unwrap(),
+                }
+            }
+        }
+    }
+}
+
+/// Orders numbers by value, with NaN above everything else. An integer
+/// sorts just before a float with the same value.
+impl Ord for YamlNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_value(other)
+            .then_with(|| self.kind().cmp(&other.kind()))
+    }
+}
+
+impl PartialOrd for YamlNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Writes integers as is and floats the way YAML reads them back as floats,
+/// e.g. `2.0`, `1e100`, `.inf` and `.nan`.
+impl fmt::Display for YamlNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.n {
+            N::PosInt(value) => write!(f, "{}", value),
+            N::NegInt(value) => write!(f, "{}", value),
+            N::Float(value) if value.is_nan() => f.write_str(".nan"),
+            N::Float(value) if value.is_infinite() => {
+                f.write_str(if value > 0.0 { ".inf" } else { "-.inf" })
+            }
+            N::Float(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl fmt::Debug for YamlNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl From<i64> for YamlNumber {
+    fn from(value: i64) -> Self {
+        let n = match u64::try_from(value) {
+            Ok(value) => N::PosInt(value),
+            Err(_) => N::NegInt(value),
+        };
+        YamlNumber { n }
+    }
+}
+
+impl From<u64> for YamlNumber {
+    fn from(value: u64) -> Self {
+        YamlNumber {
+            n: N::PosInt(value),
+        }
+    }
+}
+
+impl From<f64> for YamlNumber {
+    fn from(value: f64) -> Self {
+        YamlNumber { n: N::Float(value) }
+    }
+}
+
+impl From<i32> for YamlNumber {
+    fn from(value: i32) -> Self {
+        YamlNumber::from(i64::from(value))
+    }
+}
+
+impl From<u32> for YamlNumber {
+    fn from(value: u32) -> Self {
+        YamlNumber::from(u64::from(value))
+    }
+}
+
+impl From<f32> for YamlNumber {
+    fn from(value: f32) -> Self {
+        YamlNumber::from(f64::from(value))
+    }
+}
+
+impl From<&YamlNumber> for serde_yaml::Number {
+    fn from(number: &YamlNumber) -> Self {
+        match number.n {
+            N::PosInt(value) => serde_yaml::Number::from(value),
+            N::NegInt(value) => serde_yaml::Number::from(value),
+            N::Float(value) => serde_yaml::Number::from(value),
+        }
+    }
+}
+
+impl serde::Serialize for YamlNumber {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.n {
+            N::PosInt(value) => serializer.serialize_u64(value),
+            N::NegInt(value) => serializer.serialize_i64(value),
+            N::Float(value) => serializer.serialize_f64(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_compare_by_value() {
+        assert_eq!(YamlNumber::from(3u64), YamlNumber::from(3i64));
+        assert_ne!(YamlNumber::from(3i64), YamlNumber::from(3.0));
+        assert_eq!(YamlNumber::from(0.0), YamlNumber::from(-0.0));
+        assert_eq!(YamlNumber::from(f64::NAN), YamlNumber::from(f64::NAN));
+
+        let mut numbers = [
+            YamlNumber::from(f64::NAN),
+            YamlNumber::from(2.0),
+            YamlNumber::from(u64::MAX),
+            YamlNumber::from(2),
+            YamlNumber::from(-1.5),
+            YamlNumber::from(-7),
+        ];
+        numbers.sort();
+        let sorted: Vec<_> = numbers.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            sorted,
+            vec!["-7", "-1.5", "2", "2.0", "18446744073709551615", ".nan"]
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let unsigned = YamlNumber::from(u64::MAX);
+        assert_eq!(unsigned.as_i64(), None);
+        assert_eq!(unsigned.as_u64(), Some(u64::MAX));
+
+        let negative = YamlNumber::from(-5);
+        assert_eq!(negative.as_i64(), Some(-5));
+        assert_eq!(negative.as_u64(), None);
+        assert_eq!(negative.as_f64(), Some(-5.0));
+
+        let float = YamlNumber::from(2.0);
+        assert!(float.is_f64());
+        assert_eq!(float.as_i64(), None);
+        assert_eq!(float.to_i64_exact(), Some(2));
+        assert_eq!(YamlNumber::from(2.5).to_i64_exact(), None);
+    }
+
+    #[test]
+    fn saturating_arithmetic() {
+        let max = YamlNumber::from(i64::MAX);
+        assert_eq!(max.saturating_add(YamlNumber::from(1)), max);
+        assert_eq!(
+            YamlNumber::from(u64::MAX - 1).saturating_add(YamlNumber::from(5)),
+            YamlNumber::from(u64::MAX)
+        );
+        assert_eq!(
+            YamlNumber::from(u64::MAX).saturating_sub(YamlNumber::from(1)),
+            YamlNumber::from(u64::MAX - 1)
+        );
+        assert_eq!(
+            YamlNumber::from(1).saturating_add(YamlNumber::from(0.5)),
+            YamlNumber::from(1.5)
+        );
+        assert_eq!(YamlNumber::from(i64::MIN).saturating_neg(), max);
+        assert_eq!(
+            YamlNumber::from(u64::MAX).saturating_neg(),
+            YamlNumber::from(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(YamlNumber::from(2.0).to_string(), "2.0");
+        assert_eq!(YamlNumber::from(0.1).to_string(), "0.1");
+        assert_eq!(YamlNumber::from(f64::NEG_INFINITY).to_string(), "-.inf");
+        assert_eq!(
+            YamlNumber::from(u64::MAX).to_string(),
+            "18446744073709551615"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Paths addressing nodes inside a `YamlValue` tree.
+
+use std::fmt;
+
+use crate::yaml_value::YamlValue;
+
+/// One step of a path from the root of a `YamlValue` to one of its nodes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The value stored under this key of a mapping.
+    Key(YamlValue),
+    /// The element at this index of a sequence.
+    Index(usize),
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(YamlValue::from(key))
+    }
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathSegment::Key(YamlValue::String(key)) => f.write_str(key),
+            PathSegment::Key(key) => write!(f, "{:?}", key),
+            PathSegment::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+impl YamlValue {
+    /// Returns the node at `path`, if it exists.
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&YamlValue> {
+        path.iter().try_fold(self, |target, segment| match (target, segment) {
+            (YamlValue::Mapping(map), PathSegment::Key(key)) => map.get(key),
+            (YamlValue::Sequence(seq), PathSegment::Index(index)) => seq.get(*index),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the node at `path`, if it exists.
+    pub fn get_path_mut(&mut self, path: &[PathSegment]) -> Option<&mut YamlValue> {
+        path.iter().try_fold(self, |target, segment| match (target, segment) {
+            (YamlValue::Mapping(map), PathSegment::Key(key)) => map.get_mut(key),
+            (YamlValue::Sequence(seq), PathSegment::Index(index)) => seq.get_mut(*index),
+            _ => None,
+        })
+    }
+
+    /// Removes, at every depth, the mapping entries and sequence elements for
+    /// which `keep` returns `false`. `keep` gets the path to each node and the
+    /// node itself, parents before their children; sequence indices in the
+    /// path are the ones from before any removal. Children of a removed node
+    /// are not visited.
+    pub fn retain_recursive<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&[PathSegment], &YamlValue) -> bool,
+    {
+        retain_at(self, &mut Vec::new(), &mut keep);
+    }
+
+    /// Returns the path and the node of the first node for which `pred`
+    /// returns `true`, searching depth-first with parents before their
+    /// children, starting with this value at the empty path. Mapping keys
+    /// are not searched.
+    pub fn find<F>(&self, pred: F) -> Option<(Vec<PathSegment>, &YamlValue)>
+    where
+        F: Fn(&YamlValue) -> bool,
+    {
+        let mut path = Vec::new();
+        let found = find_at(self, &mut path, &pred)?;
+        Some((path, found))
+    }
+}
+
+/// Leaves `path` pointing at the match, if one is found.
+fn find_at<'a, F>(
+    value: &'a YamlValue,
+    path: &mut Vec<PathSegment>,
+    pred: &F,
+) -> Option<&'a YamlValue>
+where
+    F: Fn(&YamlValue) -> bool,
+{
+    if pred(value) {
+        return Some(value);
+    }
+    let mut visit = |segment: PathSegment, value: &'a YamlValue| {
+        path.push(segment);
+        let found = find_at(value, path, pred);
+        if found.is_none() {
+            path.pop();
+        }
+        found
+    };
+    match value {
+        YamlValue::Mapping(map) => map
+            .iter()
+            .find_map(|(k, v)| visit(PathSegment::Key(k.clone()), v)),
+        YamlValue::Sequence(seq) => seq
+            .iter()
+            .enumerate()
+            .find_map(|(index, value)| visit(PathSegment::Index(index), value)),
+        YamlValue::Tagged { value, .. } => find_at(value, path, pred),
+        _ => None,
+    }
+}
+
+fn retain_at<F>(value: &mut YamlValue, path: &mut Vec<PathSegment>, keep: &mut F)
+where
+    F: FnMut(&[PathSegment], &YamlValue) -> bool,
+{
+    let mut visit = |segment: PathSegment, value: &mut YamlValue| {
+        path.push(segment);
+        let kept = keep(path, value);
+        if kept {
+            retain_at(value, path, keep);
+        }
+        path.pop();
+        kept
+    };
+    match value {
+        YamlValue::Mapping(map) => map.retain(|k, v| visit(PathSegment::Key(k.clone()), v)),
+        YamlValue::Sequence(seq) => {
+            let mut index = 0;
+            seq.retain_mut(|value| {
+                index += 1;
+                visit(PathSegment::Index(index - 1), value)
+            });
+        }
+        YamlValue::Tagged { value, .. } => retain_at(value, path, keep),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_recursive_strips_nulls() {
+        let mut value = crate::yaml!({
+            "name": "app",
+            "port": null,
+            "servers": [{"host": "a", "alias": null}, null, {"host": "b"}],
+            "tls": {"cert": null, "key": {"path": null}}
+        });
+        value.retain_recursive(|_, value| *value != YamlValue::Null);
+        assert_eq!(
+            value,
+            crate::yaml!({
+                "name": "app",
+                "servers": [{"host": "a"}, {"host": "b"}],
+                "tls": {"key": {}}
+            })
+        );
+    }
+
+    #[test]
+    fn retain_recursive_inside_tags() {
+        let mut value = YamlValue::Tagged {
+            tag: "!Config".into(),
+            value: Box::new(crate::yaml!({"name": "app", "port": null})),
+        };
+        value.retain_recursive(|_, value| *value != YamlValue::Null);
+        assert_eq!(
+            value,
+            YamlValue::Tagged {
+                tag: "!Config".into(),
+                value: Box::new(crate::yaml!({"name": "app"})),
+            }
+        );
+    }
+
+    #[test]
+    fn retain_recursive_paths() {
+        let mut value = crate::yaml!({"a": [1, {"secret": 2, "b": 3}], "secret": 4});
+        let mut paths = Vec::new();
+        value.retain_recursive(|path, _| {
+            let segments: Vec<_> = path.iter().map(ToString::to_string).collect();
+            paths.push(segments.join("/"));
+            path.last() != Some(&PathSegment::from("secret"))
+        });
+        assert_eq!(value, crate::yaml!({"a": [1, {"b": 3}]}));
+        assert_eq!(
+            paths,
+            vec!["a", "a/0", "a/1", "a/1/secret", "a/1/b", "secret"]
+        );
+    }
+
+    #[test]
+    fn find_first_long_string() {
+        let value = crate::yaml!({
+            "name": "app",
+            "servers": [{"host": "a"}, {"host": "db.internal", "alias": "long-alias"}],
+            "description": "a long description"
+        });
+        let long = |value: &YamlValue| value.as_str().is_some_and(|s| s.len() > 5);
+        let (path, found) = value.find(long).unwrap();
+        assert_eq!(
+            path,
+            [
+                PathSegment::from("servers"),
+                PathSegment::from(1),
+                PathSegment::from("host")
+            ]
+        );
+        assert_eq!(found, &YamlValue::from("db.internal"));
+        assert_eq!(value.get_path(&path), Some(found));
+
+        assert_eq!(value.find(|value| value.as_str() == Some("missing")), None);
+        assert_eq!(value.find(|_| true), Some((vec![], &value)));
+    }
+}
@@ -0,0 +1,144 @@
+//! A read-only `YamlValue` whose subtrees are reference counted, so that
+//! cloning it or any part of it is cheap.
+
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::yaml_value::{pointer_keys, pointer_tokens, YamlValue};
+
+/// An immutable YAML tree whose clones share their subtrees. Build one with
+/// [`YamlValue::into_shared`] and turn it back with
+/// [`to_value`](Self::to_value).
+#[derive(Clone, Debug, PartialEq)]
+pub enum SharedYamlValue {
+    /// Any value which is not a sequence or a mapping.
+    Scalar(Rc<YamlValue>),
+    Sequence(Rc<[SharedYamlValue]>),
+    Mapping(Rc<IndexMap<YamlValue, SharedYamlValue>>),
+}
+
+impl SharedYamlValue {
+    /// Returns the value if this is a scalar.
+    pub fn as_scalar(&self) -> Option<&YamlValue> {
+        match self {
+            SharedYamlValue::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value stored under `key` if this is a mapping.
+    pub fn get(&self, key: &YamlValue) -> Option<&SharedYamlValue> {
+        match self {
+            SharedYamlValue::Mapping(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `index` if this is a sequence.
+    pub fn get_index(&self, index: usize) -> Option<&SharedYamlValue> {
+        match self {
+            SharedYamlValue::Sequence(seq) => seq.get(index),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value by a JSON Pointer, like [`YamlValue::pointer`].
+    pub fn pointer(&self, pointer: &str) -> Option<&SharedYamlValue> {
+        pointer_tokens(pointer)?.try_fold(self, |target, token| match target {
+            SharedYamlValue::Sequence(seq) => seq.get(token.parse::<usize>().ok()?),
+            SharedYamlValue::Mapping(map) => pointer_keys(&token).find_map(|key| map.get(&key)),
+            SharedYamlValue::Scalar(_) => None,
+        })
+    }
+
+    /// Returns `true` if both values point to the same shared node.
+    pub fn ptr_eq(&self, other: &SharedYamlValue) -> bool {
+        match (self, other) {
+            (SharedYamlValue::Scalar(a), SharedYamlValue::Scalar(b)) => Rc::ptr_eq(a, b),
+            (SharedYamlValue::Sequence(a), SharedYamlValue::Sequence(b)) => Rc::ptr_eq(a, b),
+            (SharedYamlValue::Mapping(a), SharedYamlValue::Mapping(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Copies this tree back into a plain `YamlValue`.
+    pub fn to_value(&self) -> YamlValue {
+        match self {
+            SharedYamlValue::Scalar(value) => YamlValue::clone(value),
+            SharedYamlValue::Sequence(seq) => {
+                YamlValue::Sequence(seq.iter().map(SharedYamlValue::to_value).collect())
+            }
+            SharedYamlValue::Mapping(map) => {
+                YamlValue::Mapping(map.iter().map(|(k, v)| (k.clone(), v.to_value())).collect())
+            }
+        }
+    }
+}
+
+impl From<YamlValue> for SharedYamlValue {
+    fn from(value: YamlValue) -> Self {
+        match value {
+            YamlValue::Sequence(seq) => {
+                SharedYamlValue::Sequence(seq.into_iter().map(SharedYamlValue::from).collect())
+            }
+            YamlValue::Mapping(map) => SharedYamlValue::Mapping(Rc::new(
+                map.into_iter()
+                    .map(|(k, v)| (k, SharedYamlValue::from(v)))
+                    .collect(),
+            )),
+            value => SharedYamlValue::Scalar(Rc::new(value)),
+        }
+    }
+}
+
+impl YamlValue {
+    /// Converts this value into a [`SharedYamlValue`], which can be cloned
+    /// in constant time and whose subtrees can be handed out without copying.
+    pub fn into_shared(self) -> SharedYamlValue {
+        SharedYamlValue::from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_match_eager_clone() {
+        let value = crate::yaml!({
+            "name": "app",
+            "servers": [{"host": "a", "port": 80}, {"host": "b"}],
+            7: [true, null]
+        });
+        let shared = value.clone().into_shared();
+        let copy = shared.clone();
+
+        assert_eq!(shared.to_value(), value);
+        for pointer in &[
+            "",
+            "/name",
+            "/servers/0/port",
+            "/servers/1",
+            "/7/0",
+            "/missing",
+        ] {
+            assert_eq!(
+                copy.pointer(pointer).map(SharedYamlValue::to_value),
+                value.pointer(pointer).cloned()
+            );
+        }
+        assert_eq!(
+            shared
+                .get(&YamlValue::from("name"))
+                .and_then(SharedYamlValue::as_scalar),
+            Some(&YamlValue::from("app"))
+        );
+        assert_eq!(shared.get_index(0), None);
+
+        let servers = shared.get(&YamlValue::from("servers")).unwrap();
+        assert!(servers.ptr_eq(copy.get(&YamlValue::from("servers")).unwrap()));
+        let rebuilt = value.clone().into_shared();
+        assert!(!servers.ptr_eq(rebuilt.get(&YamlValue::from("servers")).unwrap()));
+    }
+}
@@ -0,0 +1,112 @@
+//! A breakdown of the nodes in a `YamlValue` tree.
+
+use crate::yaml_value::YamlValue;
+
+/// Counts of each kind of node in a `YamlValue` tree, as returned by
+/// [`YamlValue::stats`]. Mapping keys are counted like any other node.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct YamlStats {
+    pub nulls: usize,
+    pub bools: usize,
+    pub numbers: usize,
+    pub strings: usize,
+    pub bytes: usize,
+    pub sequences: usize,
+    pub mappings: usize,
+    /// The number of `Tagged` nodes. The value inside is counted as well.
+    pub tagged: usize,
+    /// The number of scalars, i.e. of nodes which are not a sequence, a
+    /// mapping or tagged.
+    pub leaves: usize,
+    /// The nesting depth, as returned by [`YamlValue::depth`].
+    pub max_depth: usize,
+}
+
+impl YamlStats {
+    /// Returns the total number of nodes, the same as
+    /// [`YamlValue::node_count`].
+    pub fn node_count(&self) -> usize {
+        self.leaves + self.sequences + self.mappings + self.tagged
+    }
+}
+
+impl YamlValue {
+    /// Counts the nodes of each kind in one traversal of the tree.
+    pub fn stats(&self) -> YamlStats {
+        let mut stats = YamlStats::default();
+        let mut stack = vec![(self, 0)];
+        while let Some((value, depth)) = stack.pop() {
+            let depth = match value {
+                YamlValue::Sequence(seq) => {
+                    stats.sequences += 1;
+                    stack.extend(seq.iter().map(|v| (v, depth + 1)));
+                    depth + 1
+                }
+                YamlValue::Mapping(map) => {
+                    stats.mappings += 1;
+                    for (k, v) in map {
+                        stack.push((k, depth + 1));
+                        stack.push((v, depth + 1));
+                    }
+                    depth + 1
+                }
+                YamlValue::Tagged { value, .. } => {
+                    stats.tagged += 1;
+                    stack.push((value, depth));
+                    depth
+                }
+                scalar => {
+                    stats.leaves += 1;
+                    match scalar {
+                        YamlValue::Null => stats.nulls += 1,
+                        YamlValue::Bool(_) => stats.bools += 1,
+                        YamlValue::Number(_) => stats.numbers += 1,
+                        YamlValue::String(_) => stats.strings += 1,
+                        YamlValue::Bytes(_) => stats.bytes += 1,
+                        YamlValue::Sequence(_)
+                        | YamlValue::Mapping(_)
+                        | YamlValue::Tagged { .. } => unreachable!(),
+                    }
+                    depth
+                }
+            };
+            stats.max_depth = stats.max_depth.max(depth);
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_document() {
+        let value = crate::yaml!({
+            "name": "app",
+            "replicas": 3,
+            "debug": false,
+            "owner": null,
+            "ports": [80, 443],
+            "limits": {"cpu": 1.5, "tags": []}
+        });
+        let stats = value.stats();
+        assert_eq!(
+            stats,
+            YamlStats {
+                nulls: 1,
+                bools: 1,
+                numbers: 4,
+                strings: 9,
+                bytes: 0,
+                sequences: 2,
+                mappings: 2,
+                tagged: 0,
+                leaves: 15,
+                max_depth: 3,
+            }
+        );
+        assert_eq!(stats.node_count(), value.node_count());
+        assert_eq!(stats.max_depth, value.depth());
+    }
+}
@@ -0,0 +1,206 @@
+//! Checking a `YamlValue` against a JSON schema at runtime.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::path::PathSegment;
+use crate::yaml_value::YamlValue;
+
+/// A way in which a value does not conform to a schema, as reported by
+/// [`validate_against`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// The path of the offending node within the validated value.
+    pub path: Vec<PathSegment>,
+    pub kind: ValidationErrorKind,
+}
+
+/// The schema keyword a [`ValidationError`] was raised for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationErrorKind {
+    /// The node has none of the types allowed by `type`.
+    Type { expected: Vec<String> },
+    /// The mapping lacks this property listed under `required`.
+    Required(String),
+    /// The node is not one of the values listed under `enum`.
+    Enum,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("`")?;
+        if self.path.is_empty() {
+            f.write_str("/")?;
+        }
+        for segment in &self.path {
+            write!(f, "/{}", segment)?;
+        }
+        f.write_str("`: ")?;
+        match &self.kind {
+            ValidationErrorKind::Type { expected } => {
+                write!(f, "expected type `{}`", expected.join("` or `"))
+            }
+            ValidationErrorKind::Required(name) => {
+                write!(f, "missing required property `{}`", name)
+            }
+            ValidationErrorKind::Enum => f.write_str("not one of the allowed values"),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Checks `value` against the JSON schema `schema`, reporting every
+/// violation rather than only the first.
+///
+/// Only the `type`, `enum`, `required`, `properties` and `items` keywords are
+/// checked; others such as `$ref` or `pattern` are ignored. As in JSON Schema,
+/// a number with no fractional part is an `integer`, and numbers in `enum`
+/// match by value. `Bytes` count as a `string`, which is how they serialize,
+/// and tags are ignored.
+pub fn validate_against(value: &YamlValue, schema: &YamlValue) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    validate_at(&mut Vec::new(), value, schema, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_at(
+    path: &mut Vec<PathSegment>,
+    value: &YamlValue,
+    schema: &YamlValue,
+    errors: &mut Vec<ValidationError>,
+) {
+    let YamlValue::Mapping(schema) = schema else {
+        return;
+    };
+    let value = value.untagged();
+    let keyword = |name: &str| schema.get(&YamlValue::from(name));
+    let mut error = |path: &[PathSegment], kind| {
+        errors.push(ValidationError {
+            path: path.to_vec(),
+            kind,
+        })
+    };
+
+    let expected: Vec<&str> = match keyword("type") {
+        Some(YamlValue::String(name)) => vec![name],
+        Some(YamlValue::Sequence(names)) => names.iter().filter_map(YamlValue::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !expected.is_empty() && !expected.iter().any(|name| has_type(value, name)) {
+        let expected = expected.iter().map(|name| name.to_string()).collect();
+        error(path, ValidationErrorKind::Type { expected });
+    }
+
+    if let Some(YamlValue::Sequence(allowed)) = keyword("enum") {
+        if !allowed.iter().any(|allowed| allowed.numeric_eq(value)) {
+            error(path, ValidationErrorKind::Enum);
+        }
+    }
+
+    match value {
+        YamlValue::Mapping(map) => {
+            if let Some(YamlValue::Sequence(required)) = keyword("required") {
+                for name in required.iter().filter_map(YamlValue::as_str) {
+                    if map.get(&YamlValue::from(name)).is_none() {
+                        error(path, ValidationErrorKind::Required(name.to_string()));
+                    }
+                }
+            }
+            if let Some(YamlValue::Mapping(properties)) = keyword("properties") {
+                for (name, property_schema) in properties {
+                    if let Some(property) = map.get(name) {
+                        path.push(PathSegment::Key(name.clone()));
+                        validate_at(path, property, property_schema, errors);
+                        path.pop();
+                    }
+                }
+            }
+        }
+        YamlValue::Sequence(items) => {
+            if let Some(item_schema) = keyword("items") {
+                for (index, item) in items.iter().enumerate() {
+                    path.push(PathSegment::Index(index));
+                    validate_at(path, item, item_schema, errors);
+                    path.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether `value` is an instance of the JSON Schema type `name`.
+fn has_type(value: &YamlValue, name: &str) -> bool {
+    match (value, name) {
+        (YamlValue::Null, "null") | (YamlValue::Bool(_), "boolean") => true,
+        (YamlValue::Number(_), "number") => true,
+        (YamlValue::Number(number), "integer") => {
+            number.is_u64() || number.to_i64_exact().is_some()
+        }
+        (YamlValue::String(_), "string") | (YamlValue::Bytes(_), "string") => true,
+        (YamlValue::Sequence(_), "array") | (YamlValue::Mapping(_), "object") => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> YamlValue {
+        crate::yaml!({
+            "type": "object",
+            "required": ["name", "port"],
+            "properties": {
+                "name": {"type": "string"},
+                "port": {"type": "integer"},
+                "mode": {"enum": ["fast", "safe"]},
+                "hosts": {"type": "array", "items": {"type": "string"}}
+            }
+        })
+    }
+
+    #[test]
+    fn conforming_document() {
+        let value = crate::yaml!({"name": "a", "port": 8080.0, "mode": "safe", "hosts": ["h"]});
+        assert_eq!(validate_against(&value, &schema()), Ok(()));
+    }
+
+    #[test]
+    fn missing_required_field() {
+        let value = crate::yaml!({"name": "a"});
+        let errors = validate_against(&value, &schema()).unwrap_err();
+        assert_eq!(
+            errors,
+            [ValidationError {
+                path: vec![],
+                kind: ValidationErrorKind::Required("port".into()),
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "`/`: missing required property `port`"
+        );
+    }
+
+    #[test]
+    fn reports_every_violation_with_its_path() {
+        let value = crate::yaml!({"name": 1, "port": 1.5, "mode": "slow", "hosts": ["h", 2]});
+        let errors = validate_against(&value, &schema()).unwrap_err();
+        let messages: Vec<_> = errors.iter().map(ValidationError::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "`/name`: expected type `string`",
+                "`/port`: expected type `integer`",
+                "`/mode`: not one of the allowed values",
+                "`/hosts/1`: expected type `string`",
+            ]
+        );
+    }
+}
@@ -1,22 +1,156 @@
 use std::ops::{AddAssign, Neg, SubAssign};
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Write as _};
 use std::hash::{Hash, Hasher};
+use std::io;
+use std::str::FromStr;
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
 use serde::{Serialize, Deserialize};
 use serde_yaml::Value;
 use num_traits::ToPrimitive;
-use serde_yaml::Number;
-use crate::mapping::Mapping;
+use crate::anchor::{
+    anchor_groups, duplicate_keys, scalar_tags, syntax_error_location, AnchorGroup,
+    DuplicateKeyError,
+};
+use crate::mapping::{total_cmp, Entry, Mapping, MappingVisitor};
+use crate::number::YamlNumber;
+
+/// The maximum nesting depth accepted when deserializing a `YamlValue`.
+pub const MAX_DEPTH: usize = 128;
+
+/// An error produced while converting a `serde_yaml::Value` into a `YamlValue`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// The value is nested more deeply than the given limit.
+    DepthLimitExceeded(usize),
+    /// The number has a fractional part (or is NaN).
+    NotAnInteger(f64),
+    /// The number does not fit into an `i64`.
+    OutOfRange(f64),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::DepthLimitExceeded(limit) => {
+                write!(f, "value is nested deeper than the limit of {}", limit)
+            }
+            ConversionError::NotAnInteger(value) => write!(f, "{} is not an integer", value),
+            ConversionError::OutOfRange(value) => {
+                write!(f, "{} is out of range for an i64", value)
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+/// An error produced by [`YamlValue::parse_strict`].
+#[derive(Debug)]
+pub enum StrictParseError {
+    /// The input is not valid YAML.
+    Yaml(serde_yaml::Error),
+    /// A mapping has the same key more than once.
+    DuplicateKey(DuplicateKeyError),
+}
+
+impl fmt::Display for StrictParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StrictParseError::Yaml(err) => err.fmt(f),
+            StrictParseError::DuplicateKey(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for StrictParseError {}
+
+/// An error produced by parsing a `YamlValue` from a string with `FromStr`
+/// or [`YamlValue::parse_documents`].
+#[derive(Debug)]
+pub struct ParseError {
+    inner: serde_yaml::Error,
+    location: Option<(usize, usize)>,
+}
+
+impl ParseError {
+    /// The 1-based line of the input at which parsing failed, if known.
+    pub fn line(&self) -> Option<usize> {
+        self.location.map(|(line, _)| line)
+    }
+
+    /// The 1-based column of the input at which parsing failed, if known.
+    pub fn column(&self) -> Option<usize> {
+        self.location.map(|(_, column)| column)
+    }
+
+    /// Returns the underlying serde_yaml error.
+    pub fn into_inner(self) -> serde_yaml::Error {
+        self.inner
+    }
+}
+
+impl From<serde_yaml::Error> for ParseError {
+    fn from(inner: serde_yaml::Error) -> Self {
+        let location = inner
+            .location()
+            .map(|location| (location.line(), location.column()));
+        ParseError { inner, location }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.inner)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub enum YamlValue {
     Null,
     Bool(bool),
-    Number(i64),
+    Number(YamlNumber),
     String(String),
+    /// Binary data from a `!!binary` scalar. See
+    /// [`YamlValue::parse_tagged`].
+    Bytes(Vec<u8>),
     Sequence(Vec<YamlValue>),
     Mapping(Mapping),
+    /// A node with a custom tag such as `!Color` or `!!python/object`. See
+    /// [`YamlValue::parse_tagged`].
+    Tagged {
+        tag: String,
+        value: Box<YamlValue>,
+    },
+}
+
+/// The kind of a `YamlValue`, one per variant. See
+/// [`YamlValue::yaml_type`] and [`YamlValue::coerce_to`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YamlType {
+    Null,
+    Bool,
+    Number,
+    String,
+    Bytes,
+    Sequence,
+    Mapping,
+    Tagged,
 }
 
 impl YamlValue {
@@ -24,15 +158,17 @@ impl YamlValue {
         match &value {
             Value::Null => Self::Null,
             Value::Number(value) => {
-                let value = if let Some(value) = value.as_i64() {
-                    value
+                let number = if let Some(value) = value.as_u64() {
+                    YamlNumber::from(value)
+                } else if let Some(value) = value.as_i64() {
+                    YamlNumber::from(value)
                 } else {
                     let Some(value_f64) = value.as_f64() else {
                         panic!("Not allowed yaml value type: {:?}", value);
                     };
-                    value_f64 as i64
+                    YamlNumber::from(value_f64)
                 };
-                Self::Number(value)
+                Self::Number(number)
             }
             Value::Bool(value) => Self::Bool(*value),
             Value::String(value) => Self::String(value.clone()),
@@ -46,9 +182,996 @@ impl YamlValue {
         }
     }
 
+    /// Like [`YamlValue::new`], but fails instead of recursing when `value`
+    /// contains sequences or mappings nested more than `max_depth` levels
+    /// deep. A scalar has a depth of 0 and `[[1]]` has a depth of 2.
+    pub fn new_with_limit(value: Value, max_depth: usize) -> Result<Self, ConversionError> {
+        // Walk the tree with an explicit stack so that the check itself cannot
+        // overflow on adversarial input.
+        let mut stack = vec![(&value, 0)];
+        while let Some((value, depth)) = stack.pop() {
+            if matches!(value, Value::Sequence(_) | Value::Mapping(_)) && depth >= max_depth {
+                return Err(ConversionError::DepthLimitExceeded(max_depth));
+            }
+            match value {
+                Value::Sequence(seq) => stack.extend(seq.iter().map(|v| (v, depth + 1))),
+                Value::Mapping(map) => {
+                    for (k, v) in map {
+                        stack.push((k, depth + 1));
+                        stack.push((v, depth + 1));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self::new(value))
+    }
+
+    /// Converts a float into an integer `Number`, failing instead of
+    /// truncating when it has a fractional part or does not fit into an
+    /// `i64`.
+    pub fn from_f64_exact(value: f64) -> Result<Self, ConversionError> {
+        if value.is_nan() {
+            Err(ConversionError::NotAnInteger(value))
+        } else if value < i64::MIN as f64 || value >= i64::MAX as f64 {
+            Err(ConversionError::OutOfRange(value))
+        } else if value.fract() != 0.0 {
+            Err(ConversionError::NotAnInteger(value))
+        } else {
+            Ok(YamlValue::from(value as i64))
+        }
+    }
+
+    /// Returns the string if this is a `String`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            YamlValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Joins a sequence of strings with `sep` between them. Returns `None` if
+    /// this is not a sequence or if any element is not a `String`.
+    pub fn join(&self, sep: &str) -> Option<String> {
+        match self {
+            YamlValue::Sequence(seq) => {
+                let parts: Option<Vec<&str>> = seq.iter().map(YamlValue::as_str).collect();
+                Some(parts?.join(sep))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the number if this is an integer which fits into an `i64`.
+    /// Unlike `TryFrom`, floats such as `3.0`, strings and bools are refused.
+    pub fn as_i64_strict(&self) -> Option<i64> {
+        match self {
+            YamlValue::Number(value) => value.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for `Null` and for an empty string, byte string,
+    /// sequence or mapping. A `Number` or `Bool` always has content, so it is
+    /// never empty, not even `0` or `false`. A tagged value is empty if the
+    /// value inside is.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            YamlValue::Null => true,
+            YamlValue::Bool(_) | YamlValue::Number(_) => false,
+            YamlValue::String(value) => value.is_empty(),
+            YamlValue::Bytes(bytes) => bytes.is_empty(),
+            YamlValue::Sequence(seq) => seq.is_empty(),
+            YamlValue::Mapping(map) => map.is_empty(),
+            YamlValue::Tagged { value, .. } => value.is_empty(),
+        }
+    }
+
+    /// Returns the value inside any number of tags, or `self` if it is not
+    /// `Tagged`.
+    pub fn untagged(&self) -> &YamlValue {
+        let mut value = self;
+        while let YamlValue::Tagged { value: inner, .. } = value {
+            value = inner;
+        }
+        value
+    }
+
+    /// Returns the text of a scalar or mapping without copying when this is
+    /// already a `String`. Numbers and booleans are formatted and mappings
+    /// are serialized as YAML; `Null` and sequences give `None`.
+    pub fn to_cow_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            YamlValue::String(value) => Some(Cow::Borrowed(value)),
+            YamlValue::Number(value) => Some(Cow::Owned(value.to_string())),
+            YamlValue::Bool(value) => Some(Cow::Owned(value.to_string())),
+            YamlValue::Bytes(bytes) => Some(Cow::Owned(BASE64.encode(bytes))),
+            YamlValue::Mapping(_) => {
+                let Value::Mapping(serde_mapping) = Value::from(self) else {
+                    panic!("Failed to convert YamlValue to serde_yaml::Mapping")
+                };
+                Some(Cow::Owned(serde_yaml::to_string(&serde_mapping).unwrap()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the entries of a mapping as owned `(String, YamlValue)` pairs,
+    /// in order. Returns `None` if this is not a mapping or if any key is not
+    /// a `String`.
+    ///
+    /// This is not a `TryFrom` impl because `Vec<(String, YamlValue)>`
+    /// already converts from a sequence of pairs, through the impls for
+    /// `Vec<T>` and `(T, U)`.
+    pub fn to_string_entries(&self) -> Option<Vec<(String, YamlValue)>> {
+        match self {
+            YamlValue::Mapping(map) => map
+                .iter()
+                .map(|(k, v)| Some((k.as_str()?.to_owned(), v.clone())))
+                .collect(),
+            _ => None,
+        }
+    }
+
     pub fn parse<'a, T: TryFrom<&'a YamlValue>>(&'a self) -> Option<T> {
         T::try_from(self).ok()
     }
+
+    /// Deserializes this value into any `T: Deserialize`, so callers don't
+    /// need a `TryFrom` impl for their own types. Same as
+    /// [`FromYamlValue::from_yaml_value`](crate::de::FromYamlValue).
+    pub fn deserialize_into<T: DeserializeOwned>(&self) -> Result<T, serde_yaml::Error> {
+        T::deserialize(self)
+    }
+
+    /// Converts any `T: Serialize` into a `YamlValue` by going through
+    /// `serde_yaml::to_value`. This is the inverse of
+    /// [`deserialize_into`](Self::deserialize_into).
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::to_value(value).map(YamlValue::new)
+    }
+
+    /// Converts this value into a `serde_yaml::Value`, for code written
+    /// against serde_yaml. Same as `Value::from(&value)`; `Bytes` become
+    /// base64 strings.
+    pub fn as_serde_value(&self) -> Value {
+        Value::from(self)
+    }
+
+    /// Converts a borrowed `serde_yaml::Value`. Same as
+    /// [`YamlValue::new`], which takes the value by ownership.
+    pub fn from_serde_value(value: &Value) -> Self {
+        YamlValue::new(value.clone())
+    }
+
+    /// Parses every document of a multi-document YAML stream, where documents
+    /// are separated by `---`.
+    ///
+    /// serde_yaml does not locate syntax errors in a stream, so the location
+    /// of the error is found by scanning the input again.
+    pub fn parse_documents(input: &str) -> Result<Vec<YamlValue>, ParseError> {
+        serde_yaml::Deserializer::from_str(input)
+            .map(YamlValue::deserialize)
+            .collect::<Result<_, _>>()
+            .map_err(|err| {
+                let mut err = ParseError::from(err);
+                if err.location.is_none() {
+                    err.location = syntax_error_location(input);
+                }
+                err
+            })
+    }
+
+    /// Parses a single YAML document like `serde_yaml::from_str`, but also
+    /// decodes scalars tagged `!!binary` into `Bytes` and wraps scalars with
+    /// a custom tag such as `!Color` in `Tagged`. serde_yaml discards tags,
+    /// so neither `YamlValue::new` nor deserializing can recover them. Tags
+    /// on sequences and mappings are not recovered.
+    ///
+    /// `Bytes` are written back as plain base64 strings, and `Tagged` as
+    /// their value, by `Serialize` and by the conversion into
+    /// `serde_yaml::Value`, which cannot carry tags;
+    /// [`to_string_compact`](Self::to_string_compact) keeps the tags.
+    pub fn parse_tagged(input: &str) -> Result<YamlValue, serde_yaml::Error> {
+        let mut value: YamlValue = serde_yaml::from_str(input)?;
+        let tags = scalar_tags(input)?;
+        let has_tags = !(tags.binaries.is_empty() && tags.custom.is_empty());
+        for path in tags.binaries {
+            if let Some(node) = value.pointer_mut(&path) {
+                if let YamlValue::String(text) = node {
+                    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+                    let bytes = BASE64
+                        .decode(text)
+                        .map_err(<serde_yaml::Error as serde::de::Error>::custom)?;
+                    *node = YamlValue::Bytes(bytes);
+                }
+            }
+        }
+        for (path, tag) in tags.custom {
+            if let Some(node) = value.pointer_mut(&path) {
+                let inner = std::mem::take(node);
+                *node = YamlValue::Tagged {
+                    tag,
+                    value: Box::new(inner),
+                };
+            }
+        }
+        if has_tags {
+            // serde_yaml copied the anchored nodes before their tags were
+            // recovered, so the copies are taken again.
+            for group in anchor_groups(input)? {
+                let Some(anchored) = value.pointer(&group.anchor).cloned() else {
+                    continue;
+                };
+                for alias in &group.aliases {
+                    if let Some(node) = value.pointer_mut(alias) {
+                        *node = anchored.clone();
+                    }
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Parses a single YAML document like `serde_yaml::from_str`, but fails
+    /// on the first mapping with a repeated key instead of keeping the last
+    /// value. Only scalar keys are checked, and they are compared by the value
+    /// they resolve to, so `1` and `"1"` are different keys.
+    pub fn parse_strict(input: &str) -> Result<YamlValue, StrictParseError> {
+        let value = serde_yaml::from_str(input).map_err(StrictParseError::Yaml)?;
+        match duplicate_keys(input)
+            .map_err(StrictParseError::Yaml)?
+            .into_iter()
+            .next()
+        {
+            Some(duplicate) => Err(StrictParseError::DuplicateKey(duplicate)),
+            None => Ok(value),
+        }
+    }
+
+    /// Parses a single YAML document like [`parse_tagged`](Self::parse_tagged),
+    /// additionally reporting which nodes were shared through anchors and
+    /// aliases. See [`anchor_groups`].
+    pub fn parse_with_anchors(
+        input: &str,
+    ) -> Result<(YamlValue, Vec<AnchorGroup>), serde_yaml::Error> {
+        let value = YamlValue::parse_tagged(input)?;
+        Ok((value, anchor_groups(input)?))
+    }
+
+    /// Looks up a value by a JSON Pointer (RFC 6901) such as `/servers/0/name`.
+    ///
+    /// Sequence elements are addressed by index. Mapping entries are looked up
+    /// by string key, falling back to an integer key when the token is
+    /// numeric. The empty pointer `""` refers to the value itself.
+    pub fn pointer(&self, pointer: &str) -> Option<&YamlValue> {
+        pointer_tokens(pointer)?.try_fold(self, |target, token| match target {
+            YamlValue::Sequence(seq) => seq.get(token.parse::<usize>().ok()?),
+            YamlValue::Mapping(map) => pointer_keys(&token).find_map(|key| map.get(&key)),
+            _ => None,
+        })
+    }
+
+    /// Looks up `pointer` like [`pointer`](Self::pointer) and converts the
+    /// value found there. Returns `None` if either step fails.
+    pub fn get_typed<'a, T: TryFrom<&'a YamlValue>>(&'a self, pointer: &str) -> Option<T> {
+        self.pointer(pointer)?.parse()
+    }
+
+    /// Looks up a value by a JSON Pointer and returns a mutable reference to
+    /// it. See [`YamlValue::pointer`].
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut YamlValue> {
+        pointer_tokens(pointer)?.try_fold(self, |target, token| match target {
+            YamlValue::Sequence(seq) => seq.get_mut(token.parse::<usize>().ok()?),
+            YamlValue::Mapping(map) => {
+                let key = pointer_keys(&token).find(|key| map.contains_key(key))?;
+                map.get_mut(&key)
+            }
+            _ => None,
+        })
+    }
+
+    /// Replaces the value at each of the given JSON Pointers with `"***"`.
+    /// Pointers which do not resolve to a value are ignored.
+    pub fn redact(&mut self, paths: &[&str]) {
+        for path in paths {
+            if let Some(target) = self.pointer_mut(path) {
+                *target = YamlValue::String("***".into());
+            }
+        }
+    }
+
+    /// Compares two values, treating numbers as equal when they have the same
+    /// mathematical value regardless of how they are represented. Sequences
+    /// and mapping values are compared recursively; mapping keys must match
+    /// exactly. Use `==` for strict structural equality.
+    pub fn numeric_eq(&self, other: &YamlValue) -> bool {
+        match (self, other) {
+            (YamlValue::Number(a), YamlValue::Number(b)) => match (a.as_i64(), b.as_i64()) {
+                (Some(a), Some(b)) => a == b,
+                _ if a.is_f64() || b.is_f64() => a.as_f64() == b.as_f64(),
+                _ => a == b,
+            },
+            (YamlValue::Sequence(a), YamlValue::Sequence(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.numeric_eq(b))
+            }
+            (YamlValue::Mapping(a), YamlValue::Mapping(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, v)| b.get(k).is_some_and(|other| v.numeric_eq(other)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Orders values like `partial_cmp`, except that numbers compare by
+    /// mathematical value alone, so `2` and `2.0` are equal and `2` is
+    /// greater than `1.5`, also inside sequences. Values of different kinds
+    /// keep the total order: null < bool < number < string < bytes <
+    /// sequence < mapping < tagged, where tagged values compare by tag and
+    /// then by value.
+    pub fn cmp_numeric_aware(&self, other: &YamlValue) -> Ordering {
+        match (self, other) {
+            (YamlValue::Number(a), YamlValue::Number(b)) => a.cmp_value(b),
+            (YamlValue::Sequence(a), YamlValue::Sequence(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.cmp_numeric_aware(b))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => total_cmp(self, other),
+        }
+    }
+
+    /// Compares two values like `==`, except that a mapping entry whose value
+    /// is `Null` is treated as absent, at any depth. `{a: 1, b: null}` thus
+    /// equals `{a: 1}`.
+    pub fn eq_ignoring_nulls(&self, other: &YamlValue) -> bool {
+        fn non_null(map: &Mapping) -> impl Iterator<Item = (&YamlValue, &YamlValue)> {
+            map.iter().filter(|(_, v)| **v != YamlValue::Null)
+        }
+
+        match (self, other) {
+            (YamlValue::Sequence(a), YamlValue::Sequence(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_ignoring_nulls(b))
+            }
+            (YamlValue::Mapping(a), YamlValue::Mapping(b)) => {
+                non_null(a).count() == non_null(b).count()
+                    && non_null(a)
+                        .all(|(k, v)| b.get(k).is_some_and(|other| v.eq_ignoring_nulls(other)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Recursively replaces every `String` which parses cleanly as a bool or
+    /// an integer with the corresponding typed value, e.g. `"42"` becomes
+    /// `Number(42)` and `"true"` becomes `Bool(true)`. Floats are accepted
+    /// only when they are integral, and become integers. Any other
+    /// string, including `"42abc"`, is left as is. Mapping keys are not
+    /// touched.
+    pub fn coerce_scalars(&mut self) {
+        match self {
+            YamlValue::String(value) => {
+                if let Ok(b) = value.parse::<bool>() {
+                    *self = YamlValue::Bool(b);
+                } else if let Ok(n) = value.parse::<i64>() {
+                    *self = YamlValue::from(n);
+                } else if let Some(n) = value
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|f| YamlValue::from_f64_exact(f).ok())
+                {
+                    *self = n;
+                }
+            }
+            YamlValue::Sequence(seq) => seq.iter_mut().for_each(YamlValue::coerce_scalars),
+            YamlValue::Mapping(map) => map.iter_mut().for_each(|(_, v)| v.coerce_scalars()),
+            _ => {}
+        }
+    }
+
+    /// Recursively replaces every integer `Number` with the float of the same
+    /// value, e.g. `Number(1)` becomes `Number(1.0)`. Integers beyond 2^53
+    /// lose precision. Mapping keys are not touched.
+    pub fn numbers_to_float(&mut self) {
+        match self {
+            YamlValue::Number(n) if !n.is_f64() => *self = YamlValue::from(n.as_f64().unwrap()),
+            YamlValue::Sequence(seq) => seq.iter_mut().for_each(YamlValue::numbers_to_float),
+            YamlValue::Mapping(map) => map.iter_mut().for_each(|(_, v)| v.numbers_to_float()),
+            YamlValue::Tagged { value, .. } => value.numbers_to_float(),
+            _ => {}
+        }
+    }
+
+    /// Recursively cuts every `String` longer than `max_len` chars down to its
+    /// first `max_len` chars followed by `…`, e.g. for logging. Lengths are
+    /// counted in chars, so multibyte characters are never split. Mapping
+    /// keys are not touched.
+    pub fn truncate_strings(&mut self, max_len: usize) {
+        match self {
+            YamlValue::String(value) => {
+                if let Some((end, _)) = value.char_indices().nth(max_len) {
+                    value.truncate(end);
+                    value.push('…');
+                }
+            }
+            YamlValue::Sequence(seq) => seq.iter_mut().for_each(|v| v.truncate_strings(max_len)),
+            YamlValue::Mapping(map) => map
+                .iter_mut()
+                .for_each(|(_, v)| v.truncate_strings(max_len)),
+            YamlValue::Tagged { value, .. } => value.truncate_strings(max_len),
+            _ => {}
+        }
+    }
+
+    /// Returns which variant this value is.
+    pub fn yaml_type(&self) -> YamlType {
+        match self {
+            YamlValue::Null => YamlType::Null,
+            YamlValue::Bool(_) => YamlType::Bool,
+            YamlValue::Number(_) => YamlType::Number,
+            YamlValue::String(_) => YamlType::String,
+            YamlValue::Bytes(_) => YamlType::Bytes,
+            YamlValue::Sequence(_) => YamlType::Sequence,
+            YamlValue::Mapping(_) => YamlType::Mapping,
+            YamlValue::Tagged { .. } => YamlType::Tagged,
+        }
+    }
+
+    /// Converts this value to `target`, possibly losing information. A value
+    /// which already has the target type is returned as is; otherwise:
+    ///
+    /// - `Null`: always `Null`.
+    /// - `Bool`: a number is `true` unless zero, a string is read like
+    ///   `TryFrom` does (`yes`/`no`, `on`/`off`, ...); anything else is `false`.
+    /// - `Number`: `true` is 1 and `false` 0, a string is parsed as an integer
+    ///   or else a float; anything else is 0.
+    /// - `String`: `Null` is empty, `Bytes` become base64 and every other value
+    ///   is formatted like [`to_string_compact`](Self::to_string_compact).
+    /// - `Bytes`: a string gives its UTF-8 bytes; anything else is empty.
+    /// - `Sequence`: `Null` is empty, any other value is wrapped in a
+    ///   one-element sequence.
+    /// - `Mapping`: always empty.
+    /// - `Tagged`: there is no tag to add, so the value is returned as is.
+    ///
+    /// Coercing a tagged value to any other type coerces the value inside and
+    /// drops the tag.
+    pub fn coerce_to(&self, target: YamlType) -> YamlValue {
+        if self.yaml_type() == target {
+            return self.clone();
+        }
+        if let YamlValue::Tagged { value, .. } = self {
+            return value.coerce_to(target);
+        }
+        match target {
+            YamlType::Null => YamlValue::Null,
+            YamlType::Bool => YamlValue::Bool(match self {
+                YamlValue::Number(n) => !n.is_zero(),
+                YamlValue::String(s) => parse_yaml11_bool(s).unwrap_or(false),
+                _ => false,
+            }),
+            YamlType::Number => match self {
+                YamlValue::Bool(b) => YamlValue::from(*b as i64),
+                YamlValue::String(s) => {
+                    let s = s.trim();
+                    s.parse::<i64>()
+                        .map(YamlValue::from)
+                        .or_else(|_| s.parse::<u64>().map(YamlValue::from))
+                        .or_else(|_| s.parse::<f64>().map(YamlValue::from))
+                        .unwrap_or_else(|_| YamlValue::from(0))
+                }
+                _ => YamlValue::from(0),
+            },
+            YamlType::String => YamlValue::String(match self {
+                YamlValue::Null => String::new(),
+                YamlValue::Bytes(bytes) => BASE64.encode(bytes),
+                _ => self.to_string_compact(),
+            }),
+            YamlType::Bytes => YamlValue::Bytes(match self {
+                YamlValue::String(s) => s.clone().into_bytes(),
+                _ => Vec::new(),
+            }),
+            YamlType::Sequence => match self {
+                YamlValue::Null => YamlValue::Sequence(Vec::new()),
+                _ => YamlValue::Sequence(vec![self.clone()]),
+            },
+            YamlType::Mapping => YamlValue::Mapping(Mapping::new()),
+            YamlType::Tagged => self.clone(),
+        }
+    }
+
+    /// Appends a value to the end of a sequence. A `Null` is turned into a
+    /// one-element sequence first. On any other variant the value is handed
+    /// back unchanged.
+    pub fn push(&mut self, value: YamlValue) -> Result<(), YamlValue> {
+        match self {
+            YamlValue::Sequence(seq) => {
+                seq.push(value);
+                Ok(())
+            }
+            YamlValue::Null => {
+                *self = YamlValue::Sequence(vec![value]);
+                Ok(())
+            }
+            _ => Err(value),
+        }
+    }
+
+    /// Inserts a value at `index` in a sequence, shifting all later elements
+    /// to the right. The value is handed back if this is not a sequence or if
+    /// `index > len`.
+    pub fn seq_insert(&mut self, index: usize, value: YamlValue) -> Result<(), YamlValue> {
+        match self {
+            YamlValue::Sequence(seq) if index <= seq.len() => {
+                seq.insert(index, value);
+                Ok(())
+            }
+            _ => Err(value),
+        }
+    }
+
+    /// Removes and returns the element at `index` of a sequence. Returns
+    /// `None` if this is not a sequence or if `index` is out of bounds.
+    pub fn seq_remove(&mut self, index: usize) -> Option<YamlValue> {
+        match self {
+            YamlValue::Sequence(seq) if index < seq.len() => Some(seq.remove(index)),
+            _ => None,
+        }
+    }
+
+    /// Removes the elements of a sequence which are equal to an earlier
+    /// element, keeping the first occurrence of each. Nested sequences are
+    /// compared as a whole; see [`dedup_all`](Self::dedup_all). Does nothing
+    /// on any other variant.
+    pub fn dedup_sequence(&mut self) {
+        if let YamlValue::Sequence(seq) = self {
+            let mut seen = HashSet::new();
+            let keep: Vec<bool> = seq.iter().map(|value| seen.insert(value)).collect();
+            let mut keep = keep.into_iter();
+            seq.retain(|_| keep.next().unwrap());
+        }
+    }
+
+    /// Like [`dedup_sequence`](Self::dedup_sequence), but for every sequence
+    /// in the tree, including those inside mappings. Inner sequences are
+    /// deduplicated before the sequences containing them.
+    pub fn dedup_all(&mut self) {
+        match self {
+            YamlValue::Sequence(seq) => seq.iter_mut().for_each(YamlValue::dedup_all),
+            YamlValue::Mapping(map) => map.iter_mut().for_each(|(_, v)| v.dedup_all()),
+            YamlValue::Tagged { value, .. } => value.dedup_all(),
+            _ => {}
+        }
+        self.dedup_sequence();
+    }
+
+    /// Returns the mapping entry for `key`. A `Null` is turned into an empty
+    /// mapping first. On any other variant the key is handed back.
+    pub fn map_entry(&mut self, key: impl Into<YamlValue>) -> Result<Entry<'_>, YamlValue> {
+        if let YamlValue::Null = self {
+            *self = YamlValue::Mapping(Mapping::new());
+        }
+        match self {
+            YamlValue::Mapping(map) => Ok(map.entry(key.into())),
+            _ => Err(key.into()),
+        }
+    }
+
+    /// Takes the value out, leaving `Null` in its place.
+    pub fn take(&mut self) -> YamlValue {
+        std::mem::take(self)
+    }
+
+    /// Replaces every node equal to `from`, including this value itself, with
+    /// a copy of `to`, and returns the number of replacements. Replaced nodes
+    /// are not searched again. Mapping keys are left as they are; see
+    /// [`replace_all_including_keys`](Self::replace_all_including_keys).
+    pub fn replace_all(&mut self, from: &YamlValue, to: &YamlValue) -> usize {
+        self.replace_all_(from, to, false)
+    }
+
+    /// Like [`replace_all`](Self::replace_all), but also replaces mapping keys,
+    /// at any depth. When a replaced key collides with another key of the same
+    /// mapping, the entry that comes later wins, at the earlier position.
+    pub fn replace_all_including_keys(&mut self, from: &YamlValue, to: &YamlValue) -> usize {
+        self.replace_all_(from, to, true)
+    }
+
+    fn replace_all_(&mut self, from: &YamlValue, to: &YamlValue, keys: bool) -> usize {
+        if self == from {
+            *self = to.clone();
+            return 1;
+        }
+        match self {
+            YamlValue::Sequence(seq) => {
+                seq.iter_mut().map(|v| v.replace_all_(from, to, keys)).sum()
+            }
+            YamlValue::Mapping(map) if keys => {
+                let mut count = 0;
+                map.map_entries(|mut k, mut v| {
+                    count += k.replace_all_(from, to, keys) + v.replace_all_(from, to, keys);
+                    (k, v)
+                });
+                count
+            }
+            YamlValue::Mapping(map) => map
+                .iter_mut()
+                .map(|(_, v)| v.replace_all_(from, to, keys))
+                .sum(),
+            YamlValue::Tagged { value, .. } => value.replace_all_(from, to, keys),
+            _ => 0,
+        }
+    }
+
+    /// Replaces every key of every mapping in the tree with `f(key)`, e.g. to
+    /// normalize the casing of keys. Values are left as they are. When two
+    /// keys of the same mapping map to the same new key, the entry that comes
+    /// later wins, at the earlier position.
+    pub fn map_keys<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&YamlValue) -> YamlValue,
+    {
+        self.map_keys_(&mut f);
+    }
+
+    fn map_keys_<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&YamlValue) -> YamlValue,
+    {
+        match self {
+            YamlValue::Sequence(seq) => seq.iter_mut().for_each(|v| v.map_keys_(f)),
+            YamlValue::Mapping(map) => map.map_entries(|k, mut v| {
+                v.map_keys_(f);
+                (f(&k), v)
+            }),
+            YamlValue::Tagged { value, .. } => value.map_keys_(f),
+            _ => {}
+        }
+    }
+
+    /// Sorts the keys of every mapping in the tree, including mapping keys
+    /// that are themselves mappings. Sequence order is left untouched.
+    pub fn sort_all_keys(&mut self) {
+        match self {
+            YamlValue::Sequence(seq) => seq.iter_mut().for_each(YamlValue::sort_all_keys),
+            YamlValue::Mapping(map) => {
+                map.map_entries(|mut k, mut v| {
+                    k.sort_all_keys();
+                    v.sort_all_keys();
+                    (k, v)
+                });
+                map.sort_keys();
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns a hash of the content of this value which ignores the order of
+    /// mapping keys, so documents that differ only in key order hash the
+    /// same. Unlike the `Hash` impl, the result does not depend on the
+    /// platform or the Rust version and can be persisted, e.g. as a cache key.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        hash_canonical(self, &mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the total number of nodes in the tree, counting every scalar,
+    /// sequence and mapping, including mapping keys. A tagged value counts
+    /// as one node plus the value inside.
+    pub fn node_count(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            count += 1;
+            match value {
+                YamlValue::Sequence(seq) => stack.extend(seq),
+                YamlValue::Mapping(map) => {
+                    for (k, v) in map {
+                        stack.push(k);
+                        stack.push(v);
+                    }
+                }
+                YamlValue::Tagged { value, .. } => stack.push(value),
+                _ => {}
+            }
+        }
+        count
+    }
+
+    /// Returns how many nodes of the tree, including mapping keys and this
+    /// value itself, are equal to `target`. Nodes are visited like in
+    /// [`node_count`](Self::node_count), so matches nested inside a match
+    /// are counted too.
+    pub fn count(&self, target: &YamlValue) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            if value == target {
+                count += 1;
+            }
+            match value {
+                YamlValue::Sequence(seq) => stack.extend(seq),
+                YamlValue::Mapping(map) => {
+                    for (k, v) in map {
+                        stack.push(k);
+                        stack.push(v);
+                    }
+                }
+                YamlValue::Tagged { value, .. } => stack.push(value),
+                _ => {}
+            }
+        }
+        count
+    }
+
+    /// Formats this value on a single line in YAML flow style, e.g.
+    /// `{a: 1, b: [2, 3]}`. Strings are only quoted when needed.
+    pub fn to_string_compact(&self) -> String {
+        let mut out = String::new();
+        write_flow(self, &mut out).unwrap();
+        out
+    }
+
+    /// Writes this value as YAML to `writer` as it goes, without building
+    /// the whole output in memory first. The entries of a top-level sequence
+    /// or mapping are written in block style, one per line, each in the flow
+    /// style of [`to_string_compact`](Self::to_string_compact); any other
+    /// value is written on a single line. Wrap unbuffered writers such as a
+    /// `File` in a `BufWriter`.
+    ///
+    /// For mappings and scalars, the output reads back as the same value as
+    /// that of `to_string`. Sequences differ: `to_string` joins their
+    /// elements with `, `, which is not YAML, while this writes a block
+    /// sequence.
+    pub fn write_to<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let mut out = IoWriter {
+            inner: writer,
+            error: None,
+        };
+        let result = match self {
+            YamlValue::Sequence(seq) if !seq.is_empty() => seq.iter().try_for_each(|value| {
+                out.write_str("- ")?;
+                write_flow(value, &mut out)?;
+                out.write_char('\n')
+            }),
+            YamlValue::Mapping(map) if !map.is_empty() => map.iter().try_for_each(|(k, v)| {
+                write_flow(k, &mut out)?;
+                out.write_str(": ")?;
+                write_flow(v, &mut out)?;
+                out.write_char('\n')
+            }),
+            _ => write_flow(self, &mut out).and_then(|()| out.write_char('\n')),
+        };
+        result.map_err(|fmt::Error| {
+            out.error
+                .take()
+                .unwrap_or_else(|| io::Error::other("formatter error"))
+        })
+    }
+
+    /// Returns the maximum nesting depth of sequences and mappings, using the
+    /// same measure as [`YamlValue::new_with_limit`]: a scalar has a depth of
+    /// 0, `[]` a depth of 1 and `[[1]]` a depth of 2.
+    pub fn depth(&self) -> usize {
+        let mut max_depth = 0;
+        let mut stack = vec![(self, 0)];
+        while let Some((value, depth)) = stack.pop() {
+            let depth = match value {
+                YamlValue::Sequence(seq) => {
+                    stack.extend(seq.iter().map(|v| (v, depth + 1)));
+                    depth + 1
+                }
+                YamlValue::Mapping(map) => {
+                    for (k, v) in map {
+                        stack.push((k, depth + 1));
+                        stack.push((v, depth + 1));
+                    }
+                    depth + 1
+                }
+                YamlValue::Tagged { value, .. } => {
+                    stack.push((value, depth));
+                    depth
+                }
+                _ => depth,
+            };
+            max_depth = max_depth.max(depth);
+        }
+        max_depth
+    }
+}
+
+/// Returns the smaller of two values according to
+/// [`YamlValue::cmp_numeric_aware`], or `a` if they compare equal.
+pub fn min<'a>(a: &'a YamlValue, b: &'a YamlValue) -> &'a YamlValue {
+    if b.cmp_numeric_aware(a) == Ordering::Less {
+        b
+    } else {
+        a
+    }
+}
+
+/// Returns the larger of two values according to
+/// [`YamlValue::cmp_numeric_aware`], or `b` if they compare equal.
+pub fn max<'a>(a: &'a YamlValue, b: &'a YamlValue) -> &'a YamlValue {
+    if b.cmp_numeric_aware(a) == Ordering::Less {
+        a
+    } else {
+        b
+    }
+}
+
+/// A 64-bit FNV-1a hasher, whose output is stable across builds.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Feeds `value` to `hasher` with mapping entries sorted by key. Only
+/// explicitly little-endian integers are written, so the result is the same
+/// on every platform.
+fn hash_canonical(value: &YamlValue, hasher: &mut Fnv1a) {
+    let write_len = |hasher: &mut Fnv1a, len: usize| hasher.write(&(len as u64).to_le_bytes());
+    match value {
+        YamlValue::Null => hasher.write(&[0]),
+        YamlValue::Bool(value) => hasher.write(&[1, *value as u8]),
+        YamlValue::Number(value) => {
+            hasher.write(&[2]);
+            value.write_canonical(true, &mut |bytes| hasher.write(bytes));
+        }
+        YamlValue::String(value) => {
+            hasher.write(&[3]);
+            write_len(hasher, value.len());
+            hasher.write(value.as_bytes());
+        }
+        YamlValue::Bytes(bytes) => {
+            hasher.write(&[4]);
+            write_len(hasher, bytes.len());
+            hasher.write(bytes);
+        }
+        YamlValue::Sequence(seq) => {
+            hasher.write(&[5]);
+            write_len(hasher, seq.len());
+            seq.iter().for_each(|value| hash_canonical(value, hasher));
+        }
+        YamlValue::Mapping(map) => {
+            hasher.write(&[6]);
+            write_len(hasher, map.len());
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| total_cmp(a, b));
+            for (k, v) in entries {
+                hash_canonical(k, hasher);
+                hash_canonical(v, hasher);
+            }
+        }
+        YamlValue::Tagged { tag, value } => {
+            hasher.write(&[7]);
+            write_len(hasher, tag.len());
+            hasher.write(tag.as_bytes());
+            hash_canonical(value, hasher);
+        }
+    }
+}
+
+/// Writes `value` in YAML flow style.
+fn write_flow<W: fmt::Write>(value: &YamlValue, out: &mut W) -> fmt::Result {
+    match value {
+        YamlValue::Null => out.write_str("null"),
+        YamlValue::Bool(value) => out.write_str(if *value { "true" } else { "false" }),
+        YamlValue::Number(value) => write!(out, "{}", value),
+        YamlValue::String(value) if is_plain_scalar(value) => out.write_str(value),
+        // A JSON string is also a valid YAML double-quoted scalar.
+        YamlValue::String(value) => out.write_str(&serde_json::to_string(value).unwrap()),
+        YamlValue::Bytes(bytes) => {
+            out.write_str("!!binary ")?;
+            out.write_str(&BASE64.encode(bytes))
+        }
+        YamlValue::Sequence(seq) => {
+            out.write_char('[')?;
+            for (i, value) in seq.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_flow(value, out)?;
+            }
+            out.write_char(']')
+        }
+        YamlValue::Mapping(map) => {
+            out.write_char('{')?;
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_flow(k, out)?;
+                out.write_str(": ")?;
+                write_flow(v, out)?;
+            }
+            out.write_char('}')
+        }
+        YamlValue::Tagged { tag, value } => {
+            out.write_str(tag)?;
+            out.write_char(' ')?;
+            write_flow(value, out)
+        }
+    }
+}
+
+/// Adapts an `io::Write` for [`write_flow`], keeping the I/O error which
+/// `fmt::Error` cannot carry.
+struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Whether `s` can be written unquoted in flow style and still read back as
+/// the same string. This is deliberately conservative.
+fn is_plain_scalar(s: &str) -> bool {
+    const RESERVED: &[&str] = &["null", "true", "false", "yes", "no", "on", "off", "y", "n"];
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && !s.ends_with(' ')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./ ".contains(c))
+        && !RESERVED
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(s))
+}
+
+/// Parses a YAML 1.1 boolean word, ignoring case.
+fn parse_yaml11_bool(s: &str) -> Option<bool> {
+    const TRUE: &[&str] = &["true", "yes", "on"];
+    const FALSE: &[&str] = &["false", "no", "off"];
+    if TRUE.iter().any(|word| word.eq_ignore_ascii_case(s)) {
+        Some(true)
+    } else if FALSE.iter().any(|word| word.eq_ignore_ascii_case(s)) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Splits a JSON Pointer into its unescaped reference tokens.
+pub(crate) fn pointer_tokens(pointer: &str) -> Option<impl Iterator<Item = String> + '_> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    Some(
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~")),
+    )
+}
+
+/// The mapping keys a JSON Pointer token may refer to, in lookup order.
+pub(crate) fn pointer_keys(token: &str) -> impl Iterator<Item = YamlValue> {
+    let number = token.parse::<i64>().ok().map(YamlValue::from);
+    std::iter::once(YamlValue::String(token.to_string())).chain(number)
 }
 
 impl ToString for YamlValue {
@@ -68,31 +1191,34 @@ impl ToString for YamlValue {
     }
 }
 
+/// Parses a single YAML document like `serde_yaml::from_str`.
+impl FromStr for YamlValue {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(serde_yaml::from_str(input)?)
+    }
+}
+
 impl Default for YamlValue {
     fn default() -> Self {
         Self::Null
     }
 }
 
+/// Never fails: `Bytes` become base64 strings and tags are dropped, at any
+/// depth.
 impl From<&YamlValue> for Value {
     fn from(value: &YamlValue) -> Self {
         match value {
             YamlValue::Null => Value::Null,
             YamlValue::Bool(bool) => Value::Bool(*bool),
-            YamlValue::Number(value) => Value::Number(Number::from(value.clone())),
+            YamlValue::Number(value) => Value::Number(serde_yaml::Number::from(value)),
             YamlValue::String(value) => Value::String(value.clone()),
-            YamlValue::Sequence(value) => Value::Sequence(
-                value
-                    .iter()
-                    .map(|value| Value::try_from(value).unwrap())
-                    .collect::<Vec<_>>(),
-            ),
-            YamlValue::Mapping(value) => Value::Mapping(
-                value
-                    .iter()
-                    .map(|(key, value)| (Value::from(key), Value::from(value)))
-                    .collect::<serde_yaml::Mapping>(),
-            ),
+            YamlValue::Bytes(bytes) => Value::String(BASE64.encode(bytes)),
+            YamlValue::Sequence(value) => Value::Sequence(value.iter().map(Value::from).collect()),
+            YamlValue::Mapping(value) => Value::Mapping(value.to_serde_yaml()),
+            YamlValue::Tagged { value, .. } => Value::from(&**value),
         }
     }
 }
@@ -102,7 +1228,7 @@ impl TryFrom<&YamlValue> for i64 {
 
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(*value),
+            YamlValue::Number(value) => value.to_i64_exact().ok_or(()),
             YamlValue::String(value) => Ok(value.parse().unwrap_or_else(|_| {
                 panic!("Failed to parse i64 from string: {}", value)
             })),
@@ -117,7 +1243,10 @@ impl TryFrom<&YamlValue> for i32 {
 
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(*value as i32),
+            YamlValue::Number(value) => value
+                .to_i64_exact()
+                .and_then(|value| i32::try_from(value).ok())
+                .ok_or(()),
             YamlValue::String(value) => Ok(value.parse().unwrap_or_else(|_| {
                 panic!("Failed to parse i32 from string: {}", value)
             })),
@@ -132,7 +1261,7 @@ impl TryFrom<&YamlValue> for f64 {
 
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(*value as f64),
+            YamlValue::Number(value) => value.as_f64().ok_or(()),
             YamlValue::String(value) => Ok(value.parse().unwrap_or_else(|_| {
                 panic!("Failed to parse f64 from string: {}", value)
             })),
@@ -147,7 +1276,10 @@ impl TryFrom<&YamlValue> for u64 {
 
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(*value as u64),
+            YamlValue::Number(value) => value
+                .as_u64()
+                .or_else(|| value.to_i64_exact().and_then(|value| u64::try_from(value).ok()))
+                .ok_or(()),
             YamlValue::String(value) => Ok(value.parse().unwrap_or_else(|_| {
                 panic!("Failed to parse u64 from string: {}", value)
             })),
@@ -162,7 +1294,10 @@ impl TryFrom<&YamlValue> for u32 {
 
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(*value as u32),
+            YamlValue::Number(value) => value
+                .to_i64_exact()
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or(()),
             YamlValue::String(value) => Ok(value.parse().unwrap_or_else(|_| {
                 panic!("Failed to parse u32 from string: {}", value)
             })),
@@ -172,35 +1307,156 @@ impl TryFrom<&YamlValue> for u32 {
     }
 }
 
+/// Implements `TryFrom<&YamlValue>` for `NonZero` integers through the
+/// conversion into the underlying integer, rejecting zero. Unlike that
+/// conversion, a string which is not a valid integer is an error rather
+/// than a panic.
+macro_rules! try_from_non_zero {
+    ($($ty:ty => $int:ty),*) => {
+        $(
+            impl TryFrom<&YamlValue> for $ty {
+                type Error = ();
+
+                fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+                    let value = match value {
+                        YamlValue::String(value) => value.parse::<$int>().map_err(|_| ())?,
+                        value => <$int>::try_from(value)?,
+                    };
+                    <$ty>::new(value).ok_or(())
+                }
+            }
+        )*
+    };
+}
+
+try_from_non_zero!(
+    std::num::NonZeroU32 => u32,
+    std::num::NonZeroU64 => u64,
+    std::num::NonZeroI32 => i32,
+    std::num::NonZeroI64 => i64
+);
+
 impl TryFrom<&YamlValue> for String {
     type Error = ();
 
+    fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+        value.to_cow_str().map(Cow::into_owned).ok_or(())
+    }
+}
+
+/// Strings are read with the YAML 1.1 boolean rules: `true`/`false`,
+/// `yes`/`no` and `on`/`off`, in any case. The single-letter `y`/`n` forms
+/// are not accepted and any other string is an error. Numbers are `true`
+/// when non-zero.
+impl TryFrom<&YamlValue> for bool {
+    type Error = ();
+
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(value.to_string()),
-            YamlValue::String(value) => Ok(value.clone()),
-            YamlValue::Bool(value) => Ok(value.to_string()),
-            YamlValue::Mapping(_) => {
-                let Value::Mapping(serde_mapping) = Value::from(value) else {
-                    panic!("Failed to convert YamlValue to serde_yaml::Mapping")
-                };
-                Ok(serde_yaml::to_string(&serde_mapping).unwrap())
+            YamlValue::Number(value) => Ok(!value.is_zero()),
+            YamlValue::String(value) => parse_yaml11_bool(value).ok_or(()),
+            YamlValue::Bool(value) => Ok(*value),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Only strings convert to a path; numbers and other scalars are rejected
+/// rather than stringified.
+impl TryFrom<&YamlValue> for std::path::PathBuf {
+    type Error = ();
+
+    fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+        match value {
+            YamlValue::String(value) => Ok(std::path::PathBuf::from(value)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Keeps the kind of the number, so integers stay integers and floats stay
+/// floats. Only `Number`s convert; numeric strings are rejected.
+impl TryFrom<&YamlValue> for serde_yaml::Number {
+    type Error = ();
+
+    fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+        match value {
+            YamlValue::Number(value) => Ok(serde_yaml::Number::from(value)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Implements `TryFrom<&YamlValue>` for types parsed from a string with
+/// `FromStr`.
+macro_rules! try_from_str {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<&YamlValue> for $ty {
+                type Error = ();
+
+                fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+                    match value {
+                        YamlValue::String(value) => value.parse().map_err(|_| ()),
+                        _ => Err(()),
+                    }
+                }
             }
+        )*
+    };
+}
+
+try_from_str!(
+    std::net::IpAddr,
+    std::net::Ipv4Addr,
+    std::net::Ipv6Addr,
+    std::net::SocketAddr
+);
+
+/// Parses humantime strings such as `"30s"` or `"1h 30m"`; a non-negative
+/// number, including a float such as `1.5`, is taken as a count of seconds.
+#[cfg(feature = "humantime")]
+impl TryFrom<&YamlValue> for std::time::Duration {
+    type Error = ();
+
+    fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+        match value {
+            YamlValue::Number(value) => match value.as_u64() {
+                Some(secs) => Ok(std::time::Duration::from_secs(secs)),
+                None => value
+                    .as_f64()
+                    .and_then(|secs| std::time::Duration::try_from_secs_f64(secs).ok())
+                    .ok_or(()),
+            },
+            YamlValue::String(value) => humantime::parse_duration(value).map_err(|_| ()),
             _ => Err(()),
         }
     }
 }
 
-impl TryFrom<&YamlValue> for bool {
+/// Parses an RFC 3339 timestamp such as `"2023-01-02T03:04:05Z"`.
+#[cfg(feature = "chrono")]
+impl TryFrom<&YamlValue> for chrono::DateTime<chrono::Utc> {
     type Error = ();
 
     fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
         match value {
-            YamlValue::Number(value) => Ok(*value != 0),
-            YamlValue::String(value) => Ok(value.parse().unwrap_or_else(|_| {
-                panic!("Failed to parse bool from string: {}", value)
-            })),
-            YamlValue::Bool(value) => Ok(*value),
+            YamlValue::String(value) => chrono::DateTime::parse_from_rfc3339(value)
+                .map(|date_time| date_time.with_timezone(&chrono::Utc))
+                .map_err(|_| ()),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Parses a date such as `"2023-01-02"`.
+#[cfg(feature = "chrono")]
+impl TryFrom<&YamlValue> for chrono::NaiveDate {
+    type Error = ();
+
+    fn try_from(value: &YamlValue) -> Result<Self, Self::Error> {
+        match value {
+            YamlValue::String(value) => value.parse().map_err(|_| ()),
             _ => Err(()),
         }
     }
@@ -228,6 +1484,30 @@ impl<'a, T> TryFrom<&'a YamlValue> for Vec<T>
     }
 }
 
+/// Converts a mapping, keeping its insertion order. Fails if any key or value
+/// fails to convert.
+impl<'a, K, V> TryFrom<&'a YamlValue> for IndexMap<K, V>
+where
+    K: TryFrom<&'a YamlValue> + Hash + Eq,
+    V: TryFrom<&'a YamlValue>,
+{
+    type Error = ();
+
+    fn try_from(value: &'a YamlValue) -> Result<Self, Self::Error> {
+        match value {
+            YamlValue::Mapping(map) => map
+                .iter()
+                .map(|(k, v)| {
+                    let k = K::try_from(k).map_err(|_| ())?;
+                    let v = V::try_from(v).map_err(|_| ())?;
+                    Ok((k, v))
+                })
+                .collect(),
+            _ => Err(()),
+        }
+    }
+}
+
 impl<'a, T, U> TryFrom<&'a YamlValue> for (T, U)
     where T: TryFrom<&'a YamlValue>,
           U: TryFrom<&'a YamlValue>,
@@ -311,37 +1591,37 @@ impl<'a, T, U, V, W> TryFrom<&'a YamlValue> for (T, U, V, W)
 
 impl From<i64> for YamlValue {
     fn from(value: i64) -> Self {
-        YamlValue::Number(value)
+        YamlValue::Number(YamlNumber::from(value))
     }
 }
 
 impl From<i32> for YamlValue {
     fn from(value: i32) -> Self {
-        YamlValue::Number(value as i64)
+        YamlValue::Number(YamlNumber::from(value))
     }
 }
 
 impl From<f64> for YamlValue {
     fn from(value: f64) -> Self {
-        YamlValue::Number(value as i64)
+        YamlValue::Number(YamlNumber::from(value))
     }
 }
 
 impl From<f32> for YamlValue {
     fn from(value: f32) -> Self {
-        YamlValue::Number(value as i64)
+        YamlValue::Number(YamlNumber::from(value))
     }
 }
 
 impl From<u64> for YamlValue {
     fn from(value: u64) -> Self {
-        YamlValue::Number(value as i64)
+        YamlValue::Number(YamlNumber::from(value))
     }
 }
 
 impl From<u32> for YamlValue {
     fn from(value: u32) -> Self {
-        YamlValue::Number(value as i64)
+        YamlValue::Number(YamlNumber::from(value))
     }
 }
 
@@ -363,17 +1643,56 @@ impl From<bool> for YamlValue {
     }
 }
 
+impl<T: Into<YamlValue>> From<Vec<T>> for YamlValue {
+    fn from(value: Vec<T>) -> Self {
+        YamlValue::Sequence(value.into_iter().map(Into::into).collect())
+    }
+}
+
+/// `None` becomes `Null`.
+impl<T: Into<YamlValue>> From<Option<T>> for YamlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(YamlValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<YamlValue>> From<HashMap<String, T>> for YamlValue {
+    fn from(value: HashMap<String, T>) -> Self {
+        YamlValue::Mapping(
+            value
+                .into_iter()
+                .map(|(k, v)| (YamlValue::String(k), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: Into<YamlValue>> From<BTreeMap<String, T>> for YamlValue {
+    fn from(value: BTreeMap<String, T>) -> Self {
+        YamlValue::Mapping(
+            value
+                .into_iter()
+                .map(|(k, v)| (YamlValue::String(k), v.into()))
+                .collect(),
+        )
+    }
+}
+
 impl From<&YamlValue> for YamlValue {
     fn from(value: &YamlValue) -> Self {
         value.to_owned()
     }
 }
 
+/// Adds numbers, appends strings and extends sequences. Integer addition
+/// saturates at `i64::MIN`/`i64::MAX` instead of overflowing, or at
+/// `u64::MAX` when an operand is above `i64::MAX`. If either number is a
+/// float, the result is a float.
 impl AddAssign for YamlValue {
     fn add_assign(&mut self, rhs: Self) {
         match (self, rhs) {
             (YamlValue::Number(lhs), YamlValue::Number(rhs)) => {
-                *lhs += rhs;
+                *lhs = lhs.saturating_add(rhs);
             }
             (YamlValue::String(lhs), YamlValue::String(rhs)) => {
                 *lhs += &rhs;
@@ -386,23 +1705,26 @@ impl AddAssign for YamlValue {
     }
 }
 
+/// Subtracts numbers, saturating like the `AddAssign` impl.
 impl SubAssign for YamlValue {
     fn sub_assign(&mut self, rhs: Self) {
         match (self, rhs) {
             (YamlValue::Number(lhs), YamlValue::Number(rhs)) => {
-                *lhs -= rhs;
+                *lhs = lhs.saturating_sub(rhs);
             }
             _ => {}
         }
     }
 }
 
+/// Negates a number. Integers saturate at `i64::MIN`/`i64::MAX` instead of
+/// overflowing, e.g. `-i64::MIN` is `i64::MAX`.
 impl Neg for YamlValue {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
         match self {
-            YamlValue::Number(value) => YamlValue::Number(-value),
+            YamlValue::Number(value) => YamlValue::Number(value.saturating_neg()),
             _ => panic!("Not allowed yaml value type: {:?}", self),
         }
     }
@@ -413,7 +1735,18 @@ impl Serialize for YamlValue {
         where
             S: serde::Serializer,
     {
-        serde_yaml::Value::from(self).serialize(serializer)
+        // Serialize in place rather than through `serde_yaml::Value`, which
+        // would copy the whole tree first.
+        match self {
+            YamlValue::Null => serializer.serialize_unit(),
+            YamlValue::Bool(value) => serializer.serialize_bool(*value),
+            YamlValue::Number(value) => value.serialize(serializer),
+            YamlValue::String(value) => serializer.serialize_str(value),
+            YamlValue::Bytes(bytes) => serializer.serialize_str(&BASE64.encode(bytes)),
+            YamlValue::Sequence(seq) => serializer.collect_seq(seq),
+            YamlValue::Mapping(map) => map.serialize(serializer),
+            YamlValue::Tagged { value, .. } => value.serialize(serializer),
+        }
     }
 }
 
@@ -422,32 +1755,156 @@ impl<'a> Deserialize<'a> for YamlValue {
         where
             D: serde::Deserializer<'a>,
     {
-        let value = Value::deserialize(deserializer)?;
-        Ok(Self::new(value))
+        deserializer.deserialize_any(ValueVisitor {
+            remaining_depth: MAX_DEPTH,
+        })
+    }
+}
+
+/// Deserializes a `YamlValue` directly, without going through
+/// `serde_yaml::Value`, failing once sequences and mappings nest more than
+/// `remaining_depth` levels deep.
+#[derive(Clone, Copy)]
+pub(crate) struct ValueVisitor {
+    pub(crate) remaining_depth: usize,
+}
+
+impl ValueVisitor {
+    fn nested<E: serde::de::Error>(self) -> Result<usize, E> {
+        self.remaining_depth
+            .checked_sub(1)
+            .ok_or_else(|| E::custom(ConversionError::DepthLimitExceeded(MAX_DEPTH)))
+    }
+}
+
+impl<'de> serde::de::DeserializeSeed<'de> for ValueVisitor {
+    type Value = YamlValue;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+    type Value = YamlValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any YAML value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<YamlValue, E> {
+        Ok(YamlValue::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<YamlValue, E> {
+        Ok(YamlValue::from(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<YamlValue, E> {
+        Ok(YamlValue::from(value))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<YamlValue, E> {
+        Ok(YamlValue::from(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<YamlValue, E> {
+        Ok(YamlValue::String(value.to_owned()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<YamlValue, E> {
+        Ok(YamlValue::String(value))
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<YamlValue, E> {
+        Ok(YamlValue::Bytes(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<YamlValue, E> {
+        Ok(YamlValue::Bytes(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<YamlValue, E> {
+        Ok(YamlValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<YamlValue, E> {
+        Ok(YamlValue::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<YamlValue, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<YamlValue, A::Error>
+        where
+            A: serde::de::SeqAccess<'de>,
+    {
+        let seed = ValueVisitor {
+            remaining_depth: self.nested()?,
+        };
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element_seed(seed)? {
+            values.push(value);
+        }
+        Ok(YamlValue::Sequence(values))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<YamlValue, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+    {
+        MappingVisitor {
+            remaining_depth: self.nested()?,
+        }
+        .visit_map(map)
+        .map(YamlValue::Mapping)
     }
 }
 
 impl ToPrimitive for YamlValue {
     fn to_i64(&self) -> Option<i64> {
         match self {
-            YamlValue::Number(value) => Some(*value),
+            YamlValue::Number(value) => value.as_i64(),
             _ => None,
         }
     }
     fn to_u64(&self) -> Option<u64> {
         match self {
-            YamlValue::Number(value) => Some(*value as u64),
+            YamlValue::Number(value) => value.as_u64(),
             _ => None,
         }
     }
     fn to_f64(&self) -> Option<f64> {
         match self {
-            YamlValue::Number(value) => Some(*value as f64),
+            YamlValue::Number(value) => value.as_f64(),
             _ => None,
         }
     }
 }
 
+/// Any YAML value is accepted, so the schema is simply `true`.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for YamlValue {
+    fn schema_name() -> String {
+        "YamlValue".to_owned()
+    }
+
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::Schema::Bool(true)
+    }
+}
+
 impl Hash for YamlValue {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
@@ -457,6 +1914,995 @@ impl Hash for YamlValue {
             YamlValue::String(s) => (3, s).hash(state),
             YamlValue::Sequence(seq) => (4, seq).hash(state),
             YamlValue::Mapping(map) => (5, map).hash(state),
+            YamlValue::Bytes(bytes) => (6, bytes).hash(state),
+            YamlValue::Tagged { tag, value } => (7, tag, value).hash(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_onto_sequence() {
+        let mut value = YamlValue::Sequence(vec![YamlValue::from(1)]);
+        value.push(YamlValue::from(2)).unwrap();
+        assert_eq!(
+            value,
+            YamlValue::Sequence(vec![YamlValue::from(1), YamlValue::from(2)])
+        );
+    }
+
+    #[test]
+    fn push_onto_null() {
+        let mut value = YamlValue::Null;
+        value.push(YamlValue::from("a")).unwrap();
+        assert_eq!(value, YamlValue::Sequence(vec![YamlValue::from("a")]));
+    }
+
+    #[test]
+    fn push_onto_scalar_returns_value() {
+        let mut value = YamlValue::from(true);
+        assert_eq!(value.push(YamlValue::from(1)), Err(YamlValue::from(1)));
+        assert_eq!(value, YamlValue::from(true));
+    }
+
+    #[test]
+    fn parse_documents_splits_stream() {
+        let input = "a: 1\n---\n- x\n- y\n---\nhello\n";
+        let documents = YamlValue::parse_documents(input).unwrap();
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[0], serde_yaml::from_str::<YamlValue>("a: 1").unwrap());
+        assert_eq!(
+            documents[1],
+            YamlValue::Sequence(vec![YamlValue::from("x"), YamlValue::from("y")])
+        );
+        assert_eq!(documents[2], YamlValue::from("hello"));
+    }
+
+    #[test]
+    fn parse_with_anchors_expands_aliases() {
+        let (value, groups) = YamlValue::parse_with_anchors("a: &x [1]\nb: *x\n").unwrap();
+        let expected: YamlValue = serde_yaml::from_str("a: [1]\nb: [1]\n").unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].aliases, vec!["/b".to_string()]);
+
+        let (value, groups) =
+            YamlValue::parse_with_anchors("a: &x !!binary aGk=\nb: *x\n").unwrap();
+        assert_eq!(value.pointer("/a"), Some(&YamlValue::Bytes(b"hi".to_vec())));
+        assert_eq!(value.pointer("/b"), Some(&YamlValue::Bytes(b"hi".to_vec())));
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[test]
+    fn new_with_limit_rejects_deep_nesting() {
+        let mut value = Value::Null;
+        for _ in 0..10_000 {
+            value = Value::Sequence(vec![value]);
+        }
+        assert_eq!(
+            YamlValue::new_with_limit(value, MAX_DEPTH),
+            Err(ConversionError::DepthLimitExceeded(MAX_DEPTH))
+        );
+    }
+
+    #[test]
+    fn deserialize_rejects_deep_nesting() {
+        let mut value = Value::Null;
+        for _ in 0..MAX_DEPTH {
+            value = Value::Sequence(vec![value]);
+        }
+        assert!(YamlValue::deserialize(value.clone()).is_ok());
+        let value = Value::Mapping(std::iter::once((Value::from("a"), value)).collect());
+        assert!(YamlValue::deserialize(value).is_err());
+    }
+
+    #[test]
+    fn new_with_limit_accepts_shallow_values() {
+        let value: Value = serde_yaml::from_str("a: [[1]]").unwrap();
+        assert!(YamlValue::new_with_limit(value.clone(), 2).is_err());
+        assert_eq!(
+            YamlValue::new_with_limit(value.clone(), 3),
+            Ok(YamlValue::new(value))
+        );
+    }
+
+    fn config() -> YamlValue {
+        serde_yaml::from_str(
+            r#"
+server:
+  host: example.com
+  auth:
+    user: admin
+    token: secret
+ports: [80, 443]
+"a/b": 1
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn pointer_lookup() {
+        let value = config();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/server/auth/user"),
+            Some(&YamlValue::from("admin"))
+        );
+        assert_eq!(value.pointer("/ports/1"), Some(&YamlValue::from(443)));
+        assert_eq!(value.pointer("/a~1b"), Some(&YamlValue::from(1)));
+        assert_eq!(value.pointer("/ports/2"), None);
+        assert_eq!(value.pointer("server"), None);
+    }
+
+    #[test]
+    fn redact_nested_token() {
+        let mut value = config();
+        value.redact(&["/server/auth/token", "/server/missing", "/ports/9"]);
+        assert_eq!(
+            value.pointer("/server/auth/token"),
+            Some(&YamlValue::from("***"))
+        );
+        assert_eq!(
+            value.pointer("/server/auth/user"),
+            Some(&YamlValue::from("admin"))
+        );
+        assert_eq!(
+            value.pointer("/server/host"),
+            Some(&YamlValue::from("example.com"))
+        );
+        assert_eq!(value.pointer("/server/missing"), None);
+    }
+
+    #[test]
+    fn numeric_eq_compares_numbers_by_value() {
+        assert!(YamlValue::from(2).numeric_eq(&YamlValue::from(2)));
+        assert!(!YamlValue::from(2).numeric_eq(&YamlValue::from(3)));
+        assert!(!YamlValue::from(2).numeric_eq(&YamlValue::from("2")));
+
+        let (two, two_float) = (YamlValue::from(2), YamlValue::from(2.0));
+        assert!(two.numeric_eq(&two_float));
+        assert_ne!(two, two_float);
+        assert_eq!(two.partial_cmp(&two_float), Some(std::cmp::Ordering::Less));
+        assert_eq!(two.cmp_numeric_aware(&two_float), std::cmp::Ordering::Equal);
+        assert!(!two.numeric_eq(&YamlValue::from(2.5)));
+        assert!(crate::yaml!({"a": [2]}).numeric_eq(&crate::yaml!({"a": [2.0]})));
+
+        let a: YamlValue = serde_yaml::from_str("{a: [1, 2], b: x}").unwrap();
+        let b: YamlValue = serde_yaml::from_str("{b: x, a: [1, 2]}").unwrap();
+        let c: YamlValue = serde_yaml::from_str("{b: x, a: [1, 3]}").unwrap();
+        assert!(a.numeric_eq(&b));
+        assert!(!a.numeric_eq(&c));
+    }
+
+    #[test]
+    fn cmp_numeric_aware_orders_numbers_by_value() {
+        use std::cmp::Ordering::*;
+
+        let two = YamlValue::from(2);
+        let one_and_half = YamlValue::from(1.5);
+        assert_eq!(two.cmp_numeric_aware(&one_and_half), Greater);
+        assert_eq!(one_and_half.cmp_numeric_aware(&two), Less);
+        assert_eq!(two.cmp_numeric_aware(&YamlValue::from(2.0)), Equal);
+        assert_eq!(
+            crate::yaml!([1, 2.5]).cmp_numeric_aware(&crate::yaml!([1.0, 2])),
+            Greater
+        );
+
+        let string = YamlValue::from("1");
+        assert_eq!(string.cmp_numeric_aware(&two), Greater);
+        assert_eq!(two.cmp_numeric_aware(&string), Less);
+        assert_eq!(YamlValue::Null.cmp_numeric_aware(&two), Less);
+
+        let tagged = |tag: &str, value: YamlValue| YamlValue::Tagged {
+            tag: tag.into(),
+            value: Box::new(value),
+        };
+        let color = tagged("!Color", YamlValue::from("red"));
+        assert_eq!(color.cmp_numeric_aware(&crate::yaml!({"a": 1})), Greater);
+        assert_eq!(color.cmp_numeric_aware(&tagged("!Size", two)), Less);
+    }
+
+    #[test]
+    fn min_and_max_reduce_mixed_sequences() {
+        let values = [
+            YamlValue::from(2),
+            YamlValue::from(1.5),
+            YamlValue::from(-3),
+            YamlValue::from(2.0),
+        ];
+        assert_eq!(values.iter().reduce(min), Some(&YamlValue::from(-3)));
+        assert_eq!(values.iter().reduce(max), Some(&YamlValue::from(2.0)));
+        assert_eq!(min(&values[0], &values[3]), &YamlValue::from(2));
+    }
+
+    #[test]
+    fn serde_value_round_trip() {
+        let serde_value: Value =
+            serde_yaml::from_str("{name: a, ports: [80, 443], ratio: 0.5, on: true, x: ~}")
+                .unwrap();
+        let value = YamlValue::from_serde_value(&serde_value);
+        assert_eq!(value, YamlValue::new(serde_value.clone()));
+        assert_eq!(value.as_serde_value(), serde_value);
+        assert_eq!(YamlValue::from_serde_value(&value.as_serde_value()), value);
+    }
+
+    #[test]
+    fn from_vec() {
+        assert_eq!(
+            YamlValue::from(vec![1, 2, 3]),
+            YamlValue::Sequence(vec![
+                YamlValue::from(1),
+                YamlValue::from(2),
+                YamlValue::from(3)
+            ])
+        );
+    }
+
+    #[test]
+    fn join() {
+        assert_eq!(
+            crate::yaml!(["a", "b", "c"]).join(","),
+            Some("a,b,c".to_string())
+        );
+        assert_eq!(crate::yaml!([]).join(","), Some(String::new()));
+        assert_eq!(crate::yaml!(["a", 1]).join(","), None);
+        assert_eq!(YamlValue::from("a").join(","), None);
+    }
+
+    #[test]
+    fn as_i64_strict() {
+        assert_eq!(YamlValue::from(3).as_i64_strict(), Some(3));
+        assert_eq!(YamlValue::from(-3).as_i64_strict(), Some(-3));
+        assert_eq!(YamlValue::from(3.0).as_i64_strict(), None);
+        assert_eq!(YamlValue::from("3").as_i64_strict(), None);
+        assert_eq!(YamlValue::Bool(true).as_i64_strict(), None);
+        assert_eq!(YamlValue::from(u64::MAX).as_i64_strict(), None);
+        assert_eq!(i64::try_from(&YamlValue::from(3.0)), Ok(3));
+    }
+
+    #[test]
+    fn from_option() {
+        assert_eq!(YamlValue::from(None::<i64>), YamlValue::Null);
+        assert_eq!(YamlValue::from(Some(5)), YamlValue::from(5));
+        assert_eq!(
+            YamlValue::from(vec![Some("a"), None]),
+            crate::yaml!(["a", null])
+        );
+    }
+
+    #[test]
+    fn coerce_to_each_type() {
+        let values = [
+            YamlValue::Null,
+            YamlValue::Bool(true),
+            YamlValue::from(0),
+            YamlValue::from(" 2.5 "),
+            YamlValue::Bytes(b"hi".to_vec()),
+            crate::yaml!([1, "a"]),
+        ];
+        let coerce = |target| -> Vec<_> { values.iter().map(|v| v.coerce_to(target)).collect() };
+
+        assert!(coerce(YamlType::Null).iter().all(|v| *v == YamlValue::Null));
+        assert_eq!(
+            coerce(YamlType::Bool),
+            [false, true, false, false, false, false].map(YamlValue::Bool)
+        );
+        assert_eq!(
+            coerce(YamlType::Number),
+            [
+                YamlValue::from(0),
+                YamlValue::from(1),
+                YamlValue::from(0),
+                YamlValue::from(2.5),
+                YamlValue::from(0),
+                YamlValue::from(0),
+            ]
+        );
+        assert_eq!(
+            coerce(YamlType::String),
+            ["", "true", "0", " 2.5 ", "aGk=", "[1, a]"].map(YamlValue::from)
+        );
+        assert_eq!(
+            YamlValue::from("on").coerce_to(YamlType::Bool),
+            YamlValue::Bool(true)
+        );
+        assert_eq!(
+            YamlValue::from("-3").coerce_to(YamlType::Number),
+            YamlValue::from(-3)
+        );
+        assert_eq!(
+            YamlValue::from("hi").coerce_to(YamlType::Bytes),
+            YamlValue::Bytes(b"hi".to_vec())
+        );
+        assert_eq!(
+            YamlValue::from(1).coerce_to(YamlType::Bytes),
+            YamlValue::Bytes(vec![])
+        );
+        assert_eq!(
+            YamlValue::Null.coerce_to(YamlType::Sequence),
+            crate::yaml!([])
+        );
+        assert_eq!(
+            YamlValue::from(1).coerce_to(YamlType::Sequence),
+            crate::yaml!([1])
+        );
+        assert_eq!(values[5].coerce_to(YamlType::Mapping), crate::yaml!({}));
+    }
+
+    #[test]
+    fn from_string_maps() {
+        let expected: YamlValue = serde_yaml::from_str("{a: 1, b: 2}").unwrap();
+
+        let mut hash_map = HashMap::new();
+        hash_map.insert("a".to_string(), 1);
+        hash_map.insert("b".to_string(), 2);
+        assert_eq!(YamlValue::from(hash_map), expected);
+
+        let btree_map: BTreeMap<_, _> = vec![("a".to_string(), 1), ("b".to_string(), 2)]
+            .into_iter()
+            .collect();
+        assert_eq!(YamlValue::from(btree_map), expected);
+    }
+
+    #[test]
+    fn from_f64_exact() {
+        assert_eq!(YamlValue::from_f64_exact(2.0), Ok(YamlValue::from(2)));
+        assert_eq!(YamlValue::from_f64_exact(-0.0), Ok(YamlValue::from(0)));
+        assert_eq!(
+            YamlValue::from_f64_exact(2.5),
+            Err(ConversionError::NotAnInteger(2.5))
+        );
+        assert_eq!(
+            YamlValue::from_f64_exact(1e19),
+            Err(ConversionError::OutOfRange(1e19))
+        );
+        assert_eq!(
+            YamlValue::from_f64_exact(f64::NEG_INFINITY),
+            Err(ConversionError::OutOfRange(f64::NEG_INFINITY))
+        );
+        assert!(YamlValue::from_f64_exact(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn coerce_scalars() {
+        let mut value: YamlValue =
+            serde_yaml::from_str(r#"{a: "42", b: ["true", "42abc", "2.0"], "7": "x"}"#).unwrap();
+        value.coerce_scalars();
+        let expected: YamlValue =
+            serde_yaml::from_str(r#"{a: 42, b: [true, "42abc", 2], "7": "x"}"#).unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn seq_insert_and_remove() {
+        let mut value = YamlValue::Sequence(vec![YamlValue::from(1), YamlValue::from(3)]);
+        value.seq_insert(1, YamlValue::from(2)).unwrap();
+        assert!(value.seq_insert(4, YamlValue::from(4)).is_err());
+        assert_eq!(value.seq_remove(0), Some(YamlValue::from(1)));
+        assert_eq!(value.seq_remove(5), None);
+        assert_eq!(
+            value,
+            YamlValue::Sequence(vec![YamlValue::from(2), YamlValue::from(3)])
+        );
+    }
+
+    #[test]
+    fn take_leaves_null() {
+        let mut value = crate::yaml!({"a": [1, 2]});
+        let taken = value.pointer_mut("/a").unwrap().take();
+        assert_eq!(taken, crate::yaml!([1, 2]));
+        assert_eq!(value, crate::yaml!({"a": null}));
+    }
+
+    #[test]
+    fn node_count_and_depth() {
+        assert_eq!(YamlValue::from(1).node_count(), 1);
+        assert_eq!(YamlValue::from(1).depth(), 0);
+        assert_eq!(crate::yaml!([]).depth(), 1);
+
+        let value = crate::yaml!({"a": [1, [2]], "b": null});
+        // mapping, 2 keys, outer sequence, 1, inner sequence, 2, null
+        assert_eq!(value.node_count(), 8);
+        assert_eq!(value.depth(), 3);
+    }
+
+    #[test]
+    fn deserialize_into() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Point {
+            x: i64,
+            label: Option<String>,
+        }
+
+        let point: Point = crate::yaml!({"x": 1, "label": "a"}).deserialize_into().unwrap();
+        assert_eq!(point, Point { x: 1, label: Some("a".to_string()) });
+
+        let points: Vec<Point> = crate::yaml!([{"x": 1}, {"x": 2}]).deserialize_into().unwrap();
+        assert_eq!(points, vec![Point { x: 1, label: None }, Point { x: 2, label: None }]);
+
+        assert!(crate::yaml!({"x": "1"}).deserialize_into::<Point>().is_err());
+    }
+
+    #[test]
+    fn from_serialize() {
+        #[derive(Serialize)]
+        struct Server {
+            host: String,
+            ports: Vec<u16>,
+            tls: Option<bool>,
+        }
+
+        let server = Server {
+            host: "localhost".to_string(),
+            ports: vec![80, 443],
+            tls: None,
+        };
+        assert_eq!(
+            YamlValue::from_serialize(&server).unwrap(),
+            crate::yaml!({"host": "localhost", "ports": [80, 443], "tls": null})
+        );
+    }
+
+    #[test]
+    fn arithmetic_saturates() {
+        assert_eq!(-YamlValue::from(i64::MIN), YamlValue::from(i64::MAX));
+        assert_eq!(-YamlValue::from(5), YamlValue::from(-5));
+
+        let mut value = YamlValue::from(i64::MAX - 1);
+        value += YamlValue::from(2);
+        assert_eq!(value, YamlValue::from(i64::MAX));
+
+        let mut value = YamlValue::from(i64::MIN);
+        value -= YamlValue::from(1);
+        assert_eq!(value, YamlValue::from(i64::MIN));
+    }
+
+    #[test]
+    fn narrowing_conversions_reject_out_of_range() {
+        assert_eq!(i32::try_from(&YamlValue::from(i64::MAX)), Err(()));
+        assert_eq!(u32::try_from(&YamlValue::from(-1)), Err(()));
+        assert_eq!(u64::try_from(&YamlValue::from(-1)), Err(()));
+        assert_eq!(YamlValue::from(-1).to_u64(), None);
+        assert_eq!(u32::try_from(&YamlValue::from(7)), Ok(7));
+    }
+
+    #[test]
+    fn string_conversions() {
+        let value = YamlValue::from("hi");
+        assert_eq!(value.as_str(), Some("hi"));
+        assert_eq!(value.parse::<String>(), Some("hi".to_string()));
+        assert!(matches!(value.to_cow_str(), Some(Cow::Borrowed("hi"))));
+
+        assert_eq!(YamlValue::from(7).as_str(), None);
+        assert_eq!(YamlValue::from(7).parse::<String>(), Some("7".to_string()));
+        assert_eq!(YamlValue::Null.to_cow_str(), None);
+    }
+
+    #[cfg(feature = "humantime")]
+    #[test]
+    fn duration() {
+        use std::time::Duration;
+
+        assert_eq!(YamlValue::from("30s").parse(), Some(Duration::from_secs(30)));
+        assert_eq!(YamlValue::from("1h30m").parse(), Some(Duration::from_secs(5400)));
+        assert_eq!(YamlValue::from(90).parse(), Some(Duration::from_secs(90)));
+        assert_eq!(YamlValue::from(-1).parse::<Duration>(), None);
+        assert_eq!(YamlValue::from("soon").parse::<Duration>(), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_dates() {
+        use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+        assert_eq!(
+            YamlValue::from("2023-01-02T03:04:05Z").parse::<DateTime<Utc>>(),
+            Some(Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap())
+        );
+        assert_eq!(
+            YamlValue::from("2023-01-02T05:04:05+02:00").parse::<DateTime<Utc>>(),
+            Some(Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap())
+        );
+        assert_eq!(YamlValue::from("not-a-date").parse::<DateTime<Utc>>(), None);
+        assert_eq!(
+            YamlValue::from("2023-01-02").parse::<NaiveDate>(),
+            NaiveDate::from_ymd_opt(2023, 1, 2)
+        );
+        assert_eq!(YamlValue::from("not-a-date").parse::<NaiveDate>(), None);
+    }
+
+    #[test]
+    fn to_string_compact() {
+        let value = crate::yaml!({"a": 1, "b": [2, 3], "c": {"d": null, "e": "x y"}});
+        assert_eq!(
+            serde_yaml::to_string(&value).unwrap(),
+            "---\na: 1\nb:\n  - 2\n  - 3\nc:\n  d: ~\n  e: x y\n"
+        );
+        assert_eq!(value.to_string_compact(), "{a: 1, b: [2, 3], c: {d: null, e: x y}}");
+
+        let strings = crate::yaml!(["yes", "1", "", "a: b", "line\n", "[x]"]);
+        let compact = strings.to_string_compact();
+        assert_eq!(compact, r#"["yes", "1", "", "a: b", "line\n", "[x]"]"#);
+        assert_eq!(serde_yaml::from_str::<YamlValue>(&compact).unwrap(), strings);
+    }
+
+    #[test]
+    fn index_map_keeps_order() {
+        let value = crate::yaml!({"z": 1, "a": 2, "m": 3});
+        let map: IndexMap<String, i64> = value.parse().unwrap();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("z".to_string(), 1),
+                ("a".to_string(), 2),
+                ("m".to_string(), 3)
+            ]
+        );
+        assert_eq!(
+            crate::yaml!({"a": [1]}).parse::<IndexMap<String, i64>>(),
+            None
+        );
+    }
+
+    #[test]
+    fn eq_ignoring_nulls() {
+        let a = crate::yaml!({"a": 1, "b": null, "c": [{"d": null}]});
+        let b = crate::yaml!({"a": 1, "c": [{}]});
+        assert_ne!(a, b);
+        assert!(a.eq_ignoring_nulls(&b));
+        assert!(b.eq_ignoring_nulls(&a));
+
+        assert!(!a.eq_ignoring_nulls(&crate::yaml!({"a": 2})));
+        assert!(!crate::yaml!([null]).eq_ignoring_nulls(&crate::yaml!([])));
+    }
+
+    #[test]
+    fn get_typed() {
+        let value = crate::yaml!({"server": {"port": 8080, "hosts": ["a", "b"]}});
+        assert_eq!(value.get_typed::<i64>("/server/port"), Some(8080));
+        assert_eq!(value.get_typed::<String>("/server/hosts/1"), Some("b".to_string()));
+        assert_eq!(value.get_typed::<i64>("/server/missing"), None);
+        assert_eq!(value.get_typed::<i64>("/server/hosts"), None);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let input = "name: logo\ndata: !!binary |\n  aGVsbG8g\n  d29ybGQ=\n";
+        let value = YamlValue::parse_tagged(input).unwrap();
+        assert_eq!(
+            value,
+            crate::yaml!({"name": "logo", "data": (YamlValue::Bytes(b"hello world".to_vec()))})
+        );
+
+        let compact = value.to_string_compact();
+        assert_eq!(compact, "{name: logo, data: !!binary aGVsbG8gd29ybGQ=}");
+        assert_eq!(YamlValue::parse_tagged(&compact).unwrap(), value);
+
+        assert_eq!(
+            Value::from(&value)["data"],
+            Value::String("aGVsbG8gd29ybGQ=".to_string())
+        );
+        assert!(YamlValue::parse_tagged("!!binary '*'").is_err());
+    }
+
+    #[test]
+    fn tagged_round_trip() {
+        let value = YamlValue::parse_tagged("!Color red").unwrap();
+        let color = YamlValue::Tagged {
+            tag: "!Color".to_string(),
+            value: Box::new(YamlValue::from("red")),
+        };
+        assert_eq!(value, color);
+        assert_eq!(value.to_string_compact(), "!Color red");
+        assert_eq!(
+            YamlValue::parse_tagged(&value.to_string_compact()).unwrap(),
+            color
+        );
+
+        let value = YamlValue::parse_tagged("fg: !Color red\nbg: blue\n").unwrap();
+        assert_eq!(value.to_string_compact(), "{fg: !Color red, bg: blue}");
+        assert_eq!(
+            crate::yaml!({"fg": (color.clone()), "bg": "blue"}).content_hash(),
+            value.content_hash()
+        );
+        assert_eq!(
+            serde_yaml::to_string(&color).unwrap(),
+            serde_yaml::to_string("red").unwrap()
+        );
+        assert_eq!(color.untagged(), &YamlValue::from("red"));
+        assert_eq!(total_cmp(&color, &color), std::cmp::Ordering::Equal);
+        assert_eq!(
+            total_cmp(&crate::yaml!({}), &color),
+            std::cmp::Ordering::Less
+        );
+    }
+
+    #[test]
+    fn serialize_matches_serde_yaml_value() {
+        let value = crate::yaml!({
+            "a": [1, -2, {"b": null, "c": true}],
+            "d": "text",
+            "e": (YamlValue::Bytes(vec![0, 255])),
+            "7": []
+        });
+        let via_value = Value::from(&value);
+        assert_eq!(
+            serde_yaml::to_string(&value).unwrap(),
+            serde_yaml::to_string(&via_value).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            serde_json::to_string(&via_value).unwrap()
+        );
+    }
+
+    #[test]
+    fn sort_all_keys() {
+        let mut value = crate::yaml!({
+            "b": [{"z": 1, "x": 2}],
+            "a": {"d": {"f": 1, "e": 2}, "c": 3}
+        });
+        value.sort_all_keys();
+        assert_eq!(value.to_string_compact(), "{a: {c: 3, d: {e: 2, f: 1}}, b: [{x: 2, z: 1}]}");
+    }
+
+    #[test]
+    fn content_hash() {
+        let a = crate::yaml!({"a": 1, "b": {"c": [1, 2], "d": null}});
+        let b = crate::yaml!({"b": {"d": null, "c": [1, 2]}, "a": 1});
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        let reordered_seq = crate::yaml!({"a": 1, "b": {"c": [2, 1], "d": null}});
+        assert_ne!(a.content_hash(), reordered_seq.content_hash());
+        let string_number = crate::yaml!({"a": "1", "b": {"c": [1, 2], "d": null}});
+        assert_ne!(a.content_hash(), string_number.content_hash());
+        let (ab_c, a_bc) = (crate::yaml!(["ab", "c"]), crate::yaml!(["a", "bc"]));
+        assert_ne!(ab_c.content_hash(), a_bc.content_hash());
+        // Pin the value so that accidental changes to the encoding show up.
+        assert_eq!(YamlValue::Null.content_hash(), 0xaf63_bd4c_8601_b7df);
+    }
+
+    #[test]
+    fn path_buf() {
+        use std::path::PathBuf;
+
+        assert_eq!(YamlValue::from("/etc/app.conf").parse(), Some(PathBuf::from("/etc/app.conf")));
+        assert_eq!(YamlValue::from(42).parse::<PathBuf>(), None);
+    }
+
+    #[test]
+    fn serde_yaml_number() {
+        let int: serde_yaml::Number = YamlValue::from(-3).parse().unwrap();
+        assert!(int.is_i64() && !int.is_f64());
+        assert_eq!(int.as_i64(), Some(-3));
+
+        let float: serde_yaml::Number = YamlValue::from(2.0).parse().unwrap();
+        assert!(float.is_f64());
+        assert_eq!(float.as_f64(), Some(2.0));
+
+        assert_eq!(YamlValue::from("1").parse::<serde_yaml::Number>(), None);
+    }
+
+    #[test]
+    fn network_addresses() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+        let localhost = Ipv4Addr::new(127, 0, 0, 1);
+        assert_eq!(YamlValue::from("127.0.0.1").parse(), Some(localhost));
+        assert_eq!(YamlValue::from("127.0.0.1").parse(), Some(IpAddr::V4(localhost)));
+        assert_eq!(YamlValue::from("::1").parse(), Some(Ipv6Addr::LOCALHOST));
+        assert_eq!(YamlValue::from("::1").parse(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        assert_eq!(
+            YamlValue::from("127.0.0.1:8080").parse(),
+            Some(SocketAddr::from((localhost, 8080)))
+        );
+
+        assert_eq!(YamlValue::from("::1").parse::<Ipv4Addr>(), None);
+        assert_eq!(YamlValue::from("256.0.0.1").parse::<IpAddr>(), None);
+        assert_eq!(YamlValue::from("127.0.0.1").parse::<SocketAddr>(), None);
+        assert_eq!(YamlValue::from(80).parse::<IpAddr>(), None);
+    }
+
+    #[test]
+    fn map_entry() {
+        let mut value = crate::yaml!({"retries": 1});
+        *value.map_entry("retries").unwrap().or_insert(YamlValue::from(0)) += YamlValue::from(2);
+        value.map_entry("timeout").unwrap().or_insert(YamlValue::from(30));
+        assert_eq!(value, crate::yaml!({"retries": 3, "timeout": 30}));
+
+        let mut value = YamlValue::Null;
+        value.map_entry("name").unwrap().or_insert(YamlValue::from("app"));
+        assert_eq!(value, crate::yaml!({"name": "app"}));
+
+        let mut value = YamlValue::from(1);
+        assert_eq!(value.map_entry("name").err(), Some(YamlValue::from("name")));
+        assert_eq!(value, YamlValue::from(1));
+    }
+
+    #[test]
+    fn to_string_entries() {
+        let value = crate::yaml!({"b": 1, "a": [true]});
+        assert_eq!(
+            value.to_string_entries(),
+            Some(vec![
+                ("b".to_owned(), YamlValue::from(1)),
+                ("a".to_owned(), crate::yaml!([true])),
+            ])
+        );
+
+        assert_eq!(crate::yaml!({"a": 1, 2: 3}).to_string_entries(), None);
+        assert_eq!(crate::yaml!([1, 2]).to_string_entries(), None);
+
+        let pairs = crate::yaml!([["b", 1]]);
+        assert_eq!(
+            Vec::<(String, YamlValue)>::try_from(&pairs),
+            Ok(vec![("b".to_owned(), YamlValue::from(1))])
+        );
+        assert_eq!(Vec::<(String, YamlValue)>::try_from(&value), Err(()));
+    }
+
+    #[test]
+    fn is_empty() {
+        assert!(YamlValue::Null.is_empty());
+        assert!(YamlValue::from("").is_empty());
+        assert!(YamlValue::Bytes(vec![]).is_empty());
+        assert!(crate::yaml!([]).is_empty());
+        assert!(crate::yaml!({}).is_empty());
+
+        assert!(!YamlValue::from(0).is_empty());
+        assert!(!YamlValue::from(false).is_empty());
+        assert!(!YamlValue::from(" ").is_empty());
+        assert!(!YamlValue::Bytes(vec![0]).is_empty());
+        assert!(!crate::yaml!([null]).is_empty());
+        assert!(!crate::yaml!({"a": null}).is_empty());
+    }
+
+    #[test]
+    fn yaml11_bool_strings() {
+        assert_eq!(YamlValue::from("yes").parse(), Some(true));
+        assert_eq!(YamlValue::from("Off").parse(), Some(false));
+        assert_eq!(YamlValue::from("TRUE").parse(), Some(true));
+        assert_eq!(YamlValue::from("no").parse(), Some(false));
+        assert_eq!(YamlValue::from("maybe").parse::<bool>(), None);
+        assert_eq!(YamlValue::from("y").parse::<bool>(), None);
+    }
+
+    #[test]
+    fn numbers_round_trip() {
+        for input in &["-42\n", "18446744073709551615\n", "2.5\n", "2.0\n", "-.inf\n"] {
+            let value: YamlValue = serde_yaml::from_str(input).unwrap();
+            assert_eq!(&serde_yaml::to_string(&value).unwrap()[4..], *input);
+            assert_eq!(YamlValue::new(serde_yaml::from_str(input).unwrap()), value);
+        }
+
+        let unsigned: YamlValue = serde_yaml::from_str("18446744073709551615").unwrap();
+        assert_eq!(unsigned.parse(), Some(u64::MAX));
+        assert_eq!(unsigned.to_i64(), None);
+        let float: YamlValue = serde_yaml::from_str("2.5").unwrap();
+        assert_eq!(float.parse(), Some(2.5));
+        assert_eq!(float.parse::<i64>(), None);
+        assert_eq!(crate::yaml!({"a": 2.0}).to_string_compact(), "{a: 2.0}");
+    }
+
+    #[test]
+    fn numbers_compare_across_kinds() {
+        assert_ne!(YamlValue::from(2), YamlValue::from(2.0));
+        assert!(YamlValue::from(2).numeric_eq(&YamlValue::from(2.0)));
+        assert!(YamlValue::from(2u64).numeric_eq(&YamlValue::from(2)));
+        assert!(!YamlValue::from(2).numeric_eq(&YamlValue::from(2.5)));
+        assert_eq!(YamlValue::from(2.0).parse(), Some(2i64));
+
+        let mut value = YamlValue::from(1);
+        value += YamlValue::from(0.5);
+        assert_eq!(value, YamlValue::from(1.5));
+    }
+
+    #[test]
+    fn numbers_to_float() {
+        let mut value = crate::yaml!({"a": [1, -2, 2.5], "b": {"c": 3, "d": "4", "e": true}});
+        value.numbers_to_float();
+        assert_eq!(
+            value,
+            crate::yaml!({"a": [1.0, -2.0, 2.5], "b": {"c": 3.0, "d": "4", "e": true}})
+        );
+    }
+
+    #[test]
+    fn parse_strict_rejects_duplicate_keys() {
+        let input = "name: a\nserver:\n  port: 1\n  host: h\n  port: 2\n";
+        let lenient: YamlValue = serde_yaml::from_str(input).unwrap();
+        assert_eq!(lenient.pointer("/server/port"), Some(&YamlValue::from(2)));
+        match YamlValue::parse_strict(input) {
+            Err(StrictParseError::DuplicateKey(err)) => {
+                assert_eq!(err.path, "/server");
+                assert_eq!(err.key, "port");
+                assert_eq!(
+                    err.to_string(),
+                    "duplicate key `port` in the mapping at `/server`"
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let clean = "name: a\nserver:\n  port: 1\nclients:\n  - port: 1\n  - port: 2\n";
+        assert!(YamlValue::parse_strict("1: a\n\"1\": b\n'1.0': c\n1.0: d\n").is_ok());
+        assert!(matches!(
+            YamlValue::parse_strict("1: a\n0x1: b\n"),
+            Err(StrictParseError::DuplicateKey(_))
+        ));
+        assert_eq!(
+            YamlValue::parse_strict(clean).unwrap(),
+            serde_yaml::from_str::<YamlValue>(clean).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncate_strings() {
+        let mut value = crate::yaml!({"blob": "abcdefgh", "short": "abc", "n": 12345});
+        value.truncate_strings(3);
+        assert_eq!(value, crate::yaml!({"blob": "abc…", "short": "abc", "n": 12345}));
+
+        let mut value = crate::yaml!(["héllo", "日本語", "日本語テキスト"]);
+        value.truncate_strings(3);
+        assert_eq!(value, crate::yaml!(["hél…", "日本語", "日本語…"]));
+    }
+
+    #[test]
+    fn nested_sequence_into_value() {
+        let depth = 100;
+        let mut value = YamlValue::from(1);
+        for _ in 0..depth {
+            value = YamlValue::Sequence(vec![value, YamlValue::from("x")]);
         }
+        let yaml = format!("{}1{}", "[".repeat(depth), ", x]".repeat(depth));
+        assert_eq!(Value::from(&value), serde_yaml::from_str::<Value>(&yaml).unwrap());
+    }
+
+    #[test]
+    fn dedup_sequence() {
+        let mut value = crate::yaml!([1, 2, 2, 3, 1]);
+        value.dedup_sequence();
+        assert_eq!(value, crate::yaml!([1, 2, 3]));
+
+        let mut value = crate::yaml!([[1, 1], [1], [1, 1], {"k": ["a", "a"]}]);
+        let mut shallow = value.clone();
+        shallow.dedup_sequence();
+        assert_eq!(shallow, crate::yaml!([[1, 1], [1], {"k": ["a", "a"]}]));
+        value.dedup_all();
+        assert_eq!(value, crate::yaml!([[1], {"k": ["a"]}]));
+    }
+
+    #[test]
+    fn count() {
+        let value = crate::yaml!({
+            "name": "app",
+            "app": ["app", "web", {"app": "app"}],
+            "port": 8080
+        });
+        assert_eq!(value.count(&YamlValue::from("app")), 5);
+        assert_eq!(value.count(&YamlValue::from(8080)), 1);
+        assert_eq!(value.count(&YamlValue::from(8080.0)), 0);
+        assert_eq!(value.count(&YamlValue::from("db")), 0);
+        assert_eq!(value.count(&value), 1);
+    }
+
+    #[test]
+    fn replace_all() {
+        let mut value = crate::yaml!({
+            "host": "CHANGE_ME",
+            "CHANGE_ME": ["CHANGE_ME", "kept", {"user": "CHANGE_ME"}],
+            "port": 80
+        });
+        let from = YamlValue::from("CHANGE_ME");
+        let to = YamlValue::from("localhost");
+
+        let mut with_keys = value.clone();
+        assert_eq!(value.replace_all(&from, &to), 3);
+        assert_eq!(
+            value,
+            crate::yaml!({
+                "host": "localhost",
+                "CHANGE_ME": ["localhost", "kept", {"user": "localhost"}],
+                "port": 80
+            })
+        );
+        assert_eq!(value.replace_all(&from, &to), 0);
+
+        assert_eq!(with_keys.replace_all_including_keys(&from, &to), 4);
+        assert_eq!(with_keys.count(&to), 4);
+        assert_eq!(with_keys.count(&from), 0);
+    }
+
+    #[test]
+    fn write_to() {
+        let records: Vec<YamlValue> = (0..3)
+            .map(|i| crate::yaml!({"id": i, "name": format!("record {}", i), "tags": ["a", "b"]}))
+            .collect();
+        let value = YamlValue::Sequence(records.clone());
+
+        let mut out = Vec::new();
+        value.write_to(&mut out).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        let expected: String = records
+            .iter()
+            .map(|record| format!("- {}\n", record.to_string_compact()))
+            .collect();
+        assert_eq!(written, expected);
+
+        assert_eq!(serde_yaml::from_str::<YamlValue>(&written).unwrap(), value);
+
+        // `to_string` lays mappings out in block style, so the two are only
+        // compared as the values they read back as.
+        let same_as_to_string = |value: &YamlValue| {
+            let mut out = Vec::new();
+            value.write_to(&mut out).unwrap();
+            let written = std::str::from_utf8(&out).unwrap();
+            assert_eq!(
+                serde_yaml::from_str::<YamlValue>(written).unwrap(),
+                serde_yaml::from_str::<YamlValue>(&value.to_string()).unwrap()
+            );
+            out
+        };
+        let map = crate::yaml!({"a": 1, "b": [true, null], "c": {"d": "x y"}});
+        assert_eq!(same_as_to_string(&map), b"a: 1\nb: [true, null]\nc: {d: x y}\n");
+        assert_eq!(same_as_to_string(&YamlValue::from("x")), b"x\n");
+        same_as_to_string(&YamlValue::from(2.5));
+    }
+
+    #[test]
+    fn non_zero_integers() {
+        use std::num::{NonZeroI64, NonZeroU32};
+
+        let port = NonZeroU32::try_from(&YamlValue::from(8080)).unwrap();
+        assert_eq!(port.get(), 8080);
+        assert_eq!(
+            NonZeroI64::try_from(&YamlValue::from(-3)).map(NonZeroI64::get),
+            Ok(-3)
+        );
+        assert_eq!(NonZeroU32::try_from(&YamlValue::from(0)), Err(()));
+        assert_eq!(NonZeroU32::try_from(&YamlValue::from(-1)), Err(()));
+        assert_eq!(NonZeroI64::try_from(&YamlValue::Null), Err(()));
+
+        assert_eq!(NonZeroU32::try_from(&YamlValue::from("42")).map(NonZeroU32::get), Ok(42));
+        assert_eq!(NonZeroU32::try_from(&YamlValue::from("abc")), Err(()));
+        assert_eq!(NonZeroU32::try_from(&YamlValue::from("0")), Err(()));
+        assert_eq!(NonZeroU32::try_from(&YamlValue::from("99999999999")), Err(()));
+    }
+
+    #[test]
+    fn parse_error_location() {
+        let value: YamlValue = "name: app\nports: [80, 443]\n".parse().unwrap();
+        assert_eq!(value.pointer("/ports/1"), Some(&YamlValue::from(443)));
+
+        let err = "name: app\nports: [80, 443\n".parse::<YamlValue>().unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(3), Some(1)));
+
+        let err = YamlValue::parse_documents("a: 1\n---\nb: [1\n  c: d\n").unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(4), Some(4)));
+    }
+
+    #[test]
+    fn map_keys() {
+        let mut value = crate::yaml!({
+            "Name": "App",
+            "Servers": [{"Host": "A", "PORT": 80}],
+            "Limits": {"CPU": 2, "cpu": 4, "Memory": "1G"}
+        });
+        value.map_keys(|k| match k.as_str() {
+            Some(k) => YamlValue::from(k.to_lowercase()),
+            None => k.clone(),
+        });
+        assert_eq!(
+            value,
+            crate::yaml!({
+                "name": "App",
+                "servers": [{"host": "A", "port": 80}],
+                "limits": {"cpu": 4, "memory": "1G"}
+            })
+        );
     }
 }
@@ -1,9 +1,14 @@
-use crate::Expander;
-use crate::Schema;
+use crate::{Expander, FieldCase, RefSchema, Visibility};
+use crate::{ref_file, ref_root_name, Schema};
+
+use inflector::Inflector;
+use serde::Deserialize;
 
 use std::{
+    collections::{BTreeSet, HashMap},
     io,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 /// A configurable builder for generating Rust types from a JSON
@@ -25,6 +30,56 @@ pub struct Generator<'a, 'b> {
     pub schemafy_path: &'a str,
     /// The JSON schema file to read
     pub input_file: &'b Path,
+    /// Emit `Default` impls for the generated types. See
+    /// [`Expander::with_derive_default`](../struct.Expander.html#method.with_derive_default)
+    /// for how required fields and schema defaults are handled.
+    pub derive_default: bool,
+    /// Emit `schemars::JsonSchema` impls for the generated types. Defaults
+    /// to whether the `schemars` feature is enabled. See
+    /// [`Expander::with_derive_json_schema`](../struct.Expander.html#method.with_derive_json_schema).
+    pub derive_json_schema: bool,
+    /// Emit a builder for each generated struct. See
+    /// [`Expander::with_derive_builder`](../struct.Expander.html#method.with_derive_builder).
+    pub derive_builder: bool,
+    /// The casing of generated field names, snake_case by default. See
+    /// [`Expander::with_field_case`](../struct.Expander.html#method.with_field_case).
+    pub field_case: FieldCase,
+    /// The visibility of generated items, `pub` by default. See
+    /// [`Expander::with_visibility`](../struct.Expander.html#method.with_visibility).
+    pub visibility: Visibility,
+    /// Start files written by
+    /// [`generate_to_file`](#method.generate_to_file) with a `//!` comment
+    /// naming the input file and the time of generation. The file must then
+    /// be used as a module rather than through `include!`. In
+    /// [`compile_schemas_with`](crate::compile_schemas_with), one `//`
+    /// comment naming every schema file starts `resource.rs` instead.
+    pub provenance_header: bool,
+    /// Compile the generated items only when a cargo feature is enabled.
+    pub feature_gate: FeatureGate,
+    /// The root type names of schema files which may be referenced with
+    /// `$ref`, keyed by their canonical path. A referenced file missing here
+    /// is named after its file name, e.g. `user.v2` for
+    /// `user.v2.schema.yaml`. [`compile_schemas_named`](crate::compile_schemas_named)
+    /// fills this in with the names it gives every schema file.
+    pub ref_root_names: HashMap<PathBuf, String>,
+}
+
+/// The cargo feature, if any, which the generated items are gated behind
+/// with `#[cfg(feature = "...")]`. See [`Generator::feature_gate`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FeatureGate {
+    /// The items are always compiled.
+    #[default]
+    Off,
+    /// The feature is named after the root type in snake_case, e.g.
+    /// `user_profile` for `UserProfile`. For
+    /// [`generate_documents`](Generator::generate_documents), every document
+    /// gets its own feature. Without a root name, the schema `title` is used
+    /// instead, or else the input file name without its extension, e.g.
+    /// `user_profile` for `user-profile.schema.yaml`.
+    RootName,
+    /// The feature with this name.
+    Feature(String),
 }
 
 impl<'a, 'b> Generator<'a, 'b> {
@@ -34,6 +89,66 @@ impl<'a, 'b> Generator<'a, 'b> {
     }
 
     pub fn generate(&self) -> proc_macro2::TokenStream {
+        let (input_file, yaml) = self.read_input();
+
+        let schema = serde_yaml::from_str::<Schema>(&yaml).unwrap_or_else(|err| {
+            panic!(
+                "Cannot parse `{}` as JSON: {}",
+                input_file.to_string_lossy(),
+                err
+            )
+        });
+
+        let value = serde_yaml::from_str(&yaml).unwrap_or(serde_yaml::Value::Null);
+        let ref_schemas = load_ref_schemas(&input_file, &value, &self.ref_root_names);
+        self.expand(self.root_name.as_deref(), &schema, &ref_schemas)
+    }
+
+    /// Generates types for every schema in a multi-document YAML file, where
+    /// documents are separated by `---`. The root type of each document is
+    /// named after the string value of its `name_field` (e.g. `title`), and
+    /// `root_name` is ignored.
+    pub fn generate_documents(&self, name_field: &str) -> proc_macro2::TokenStream {
+        let (input_file, yaml) = self.read_input();
+
+        serde_yaml::Deserializer::from_str(&yaml)
+            .enumerate()
+            .map(|(index, document)| {
+                let value = serde_yaml::Value::deserialize(document).unwrap_or_else(|err| {
+                    panic!(
+                        "Cannot parse document {} of `{}`: {}",
+                        index,
+                        input_file.to_string_lossy(),
+                        err
+                    )
+                });
+                let root_name = value
+                    .get(name_field)
+                    .and_then(serde_yaml::Value::as_str)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Document {} of `{}` has no string `{}` field",
+                            index,
+                            input_file.to_string_lossy(),
+                            name_field
+                        )
+                    })
+                    .to_string();
+                let ref_schemas = load_ref_schemas(&input_file, &value, &self.ref_root_names);
+                let schema = serde_yaml::from_value::<Schema>(value).unwrap_or_else(|err| {
+                    panic!(
+                        "Cannot parse document {} of `{}` as a schema: {}",
+                        index,
+                        input_file.to_string_lossy(),
+                        err
+                    )
+                });
+                self.expand(Some(&root_name), &schema, &ref_schemas)
+            })
+            .collect()
+    }
+
+    fn read_input(&self) -> (PathBuf, String) {
         let input_file = if self.input_file.is_relative() {
             let crate_root = get_crate_root().unwrap();
             crate_root.join(self.input_file)
@@ -45,22 +160,61 @@ impl<'a, 'b> Generator<'a, 'b> {
             panic!("Unable to read `{}`: {}", input_file.to_string_lossy(), err)
         });
 
-        let schema = serde_yaml::from_str::<Schema>(&yaml).unwrap_or_else(|err| {
-            panic!(
-                "Cannot parse `{}` as JSON: {}",
-                input_file.to_string_lossy(),
-                err
-            )
-        });
+        (input_file, yaml)
+    }
+
+    fn expand(
+        &self,
+        root_name: Option<&str>,
+        schema: &Schema,
+        ref_schemas: &HashMap<String, RefSchema>,
+    ) -> proc_macro2::TokenStream {
+        let mut expander = Expander::new(root_name, self.schemafy_path, schema)
+            .with_ref_schemas(ref_schemas)
+            .with_derive_default(self.derive_default)
+            .with_derive_json_schema(self.derive_json_schema)
+            .with_derive_builder(self.derive_builder)
+            .with_field_case(self.field_case)
+            .with_visibility(self.visibility);
+        let tokens = expander.expand(schema);
+        self.gate(root_name, schema, tokens)
+    }
 
-        let mut expander = Expander::new(self.root_name.as_deref(), self.schemafy_path, &schema);
-        expander.expand(&schema)
+    /// Adds `#[cfg(feature = "...")]` to every top-level item of `tokens`, as
+    /// configured by `feature_gate`.
+    fn gate(
+        &self,
+        root_name: Option<&str>,
+        schema: &Schema,
+        tokens: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let feature = match &self.feature_gate {
+            FeatureGate::Off => return tokens,
+            FeatureGate::RootName => {
+                let file_name = self.input_file.file_name().unwrap_or_default();
+                let file_name = file_name.to_string_lossy();
+                root_name
+                    .or(schema.title.as_deref())
+                    .unwrap_or_else(|| ref_root_name(&file_name))
+                    .to_snake_case()
+            }
+            FeatureGate::Feature(feature) => feature.clone(),
+        };
+        let file: syn::File = syn::parse2(tokens).expect("Generated code is not valid Rust");
+        file.items
+            .iter()
+            .map(|item| quote! { #[cfg(feature = #feature)] #item })
+            .collect()
     }
 
     pub fn generate_to_file<P: ?Sized + AsRef<Path>>(&self, output_file: &'b P) -> io::Result<()> {
         use std::process::Command;
         let tokens = self.generate();
-        let out = tokens.to_string();
+        let mut out = String::new();
+        if self.provenance_header {
+            out.push_str(&self.provenance_header());
+        }
+        out.push_str(&tokens.to_string());
         std::fs::write(output_file, &out)?;
         Command::new("rustfmt")
             .arg(output_file.as_ref().as_os_str())
@@ -68,6 +222,14 @@ impl<'a, 'b> Generator<'a, 'b> {
         Ok(())
     }
 
+    fn provenance_header(&self) -> String {
+        format!(
+            "//! Generated by schemafy from `{}`.\n//! Generated at {}.\n\n",
+            self.input_file.display(),
+            now_timestamp()
+        )
+    }
+
     pub fn append_to_string(&self, output_str: &mut String) -> io::Result<()> {
         let tokens = self.generate();
         let out = tokens.to_string();
@@ -90,6 +252,14 @@ impl<'a, 'b> Default for GeneratorBuilder<'a, 'b> {
                 root_name: None,
                 schemafy_path: "::schemafy_core::",
                 input_file: Path::new("schema.json"),
+                derive_default: false,
+                derive_json_schema: cfg!(feature = "schemars"),
+                derive_builder: false,
+                field_case: FieldCase::Snake,
+                visibility: Visibility::Public,
+                provenance_header: false,
+                feature_gate: FeatureGate::Off,
+                ref_root_names: HashMap::new(),
             },
         }
     }
@@ -112,11 +282,143 @@ impl<'a, 'b> GeneratorBuilder<'a, 'b> {
         self.inner.schemafy_path = schemafy_path;
         self
     }
+    pub fn with_derive_default(mut self, derive_default: bool) -> Self {
+        self.inner.derive_default = derive_default;
+        self
+    }
+    pub fn with_derive_json_schema(mut self, derive_json_schema: bool) -> Self {
+        self.inner.derive_json_schema = derive_json_schema;
+        self
+    }
+    pub fn with_derive_builder(mut self, derive_builder: bool) -> Self {
+        self.inner.derive_builder = derive_builder;
+        self
+    }
+    pub fn with_field_case(mut self, field_case: FieldCase) -> Self {
+        self.inner.field_case = field_case;
+        self
+    }
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.inner.visibility = visibility;
+        self
+    }
+    pub fn with_provenance_header(mut self, provenance_header: bool) -> Self {
+        self.inner.provenance_header = provenance_header;
+        self
+    }
+    pub fn with_feature_gate(mut self, feature_gate: FeatureGate) -> Self {
+        self.inner.feature_gate = feature_gate;
+        self
+    }
+    /// Sets [`Generator::ref_root_names`]. The paths need not be canonical.
+    pub fn with_ref_root_names(mut self, ref_root_names: HashMap<PathBuf, String>) -> Self {
+        self.inner.ref_root_names = ref_root_names
+            .into_iter()
+            .map(|(path, name)| (std::fs::canonicalize(&path).unwrap_or(path), name))
+            .collect();
+        self
+    }
     pub fn build(self) -> Generator<'a, 'b> {
         self.inner
     }
 }
 
+/// Loads the schema files referenced by a `$ref` anywhere in `schema`, keyed
+/// by the file as written in the `$ref`, and names their root types from
+/// `root_names`. Files are looked up relative to the directory of
+/// `input_file`; their own `$ref`s to other files are not followed.
+fn load_ref_schemas(
+    input_file: &Path,
+    schema: &serde_yaml::Value,
+    root_names: &HashMap<PathBuf, String>,
+) -> HashMap<String, RefSchema> {
+    let mut refs = Vec::new();
+    collect_refs(schema, &mut refs);
+    let dir = input_file.parent().unwrap_or_else(|| Path::new(""));
+    let files: BTreeSet<&str> = refs.into_iter().map(ref_file).collect();
+    files
+        .into_iter()
+        .filter(|file| !file.is_empty())
+        .map(|file| {
+            let path = dir.join(file);
+            let yaml = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+                panic!("Unable to read `{}`: {}", path.to_string_lossy(), err)
+            });
+            let schema = serde_yaml::from_str::<Schema>(&yaml).unwrap_or_else(|err| {
+                panic!("Cannot parse `{}` as JSON: {}", path.to_string_lossy(), err)
+            });
+            let root_name = std::fs::canonicalize(&path)
+                .ok()
+                .and_then(|path| root_names.get(&path))
+                .cloned()
+                .unwrap_or_else(|| ref_root_name(file).to_owned());
+            (file.to_owned(), RefSchema { root_name, schema })
+        })
+        .collect()
+}
+
+fn collect_refs<'v>(value: &'v serde_yaml::Value, refs: &mut Vec<&'v str>) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value.as_str()) {
+                    (Some("$ref"), Some(ref_)) => refs.push(ref_),
+                    _ => collect_refs(value, refs),
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(seq) => seq.iter().for_each(|value| collect_refs(value, refs)),
+        _ => {}
+    }
+}
+
+/// The provenance header for code generated from several schema files into
+/// one file, as by [`compile_schemas_with`](crate::compile_schemas_with).
+/// The file is `include!`d, so plain comments are used instead of inner doc
+/// comments.
+pub(crate) fn combined_provenance_header(sources: &[&Path]) -> String {
+    let mut header = String::from("// Generated by schemafy from:\n");
+    for source in sources {
+        header.push_str(&format!("// - `{}`\n", source.display()));
+    }
+    header.push_str(&format!("// Generated at {}.\n\n", now_timestamp()));
+    header
+}
+
+fn now_timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    utc_timestamp(now)
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp, such
+/// as `2024-01-31T12:00:00Z`.
+fn utc_timestamp(secs: u64) -> String {
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    // Converts days since 1970-01-01 to a civil date, counting in 400 year
+    // eras which start on March 1st so that leap days come last.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
 fn get_crate_root() -> std::io::Result<PathBuf> {
     if let Ok(path) = std::env::var("CARGO_MANIFEST_DIR") {
         return Ok(PathBuf::from(path));
@@ -136,3 +438,43 @@ fn get_crate_root() -> std::io::Result<PathBuf> {
 
     Ok(current_dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_timestamps() {
+        assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(utc_timestamp(1_709_210_096), "2024-02-29T12:34:56Z");
+        assert_eq!(utc_timestamp(4_107_628_799), "2100-03-01T23:59:59Z");
+    }
+
+    #[test]
+    fn root_name_feature_gate_without_root_name() {
+        let gated_by = |input_file: &Path| {
+            Generator::builder()
+                .with_input_file(input_file)
+                .with_feature_gate(FeatureGate::RootName)
+                .build()
+                .generate()
+                .to_string()
+        };
+
+        let titled = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../tests/nested.json"));
+        assert!(gated_by(titled).contains(r#"feature = "nestedtest""#));
+
+        let dir = std::env::temp_dir().join(format!("schemafy-gate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let untitled = dir.join("user-profile.schema.yaml");
+        std::fs::write(
+            &untitled,
+            "definitions:\n  name:\n    type: object\n    properties:\n      first:\n        type: string\n",
+        )
+        .unwrap();
+        let tokens = gated_by(&untitled);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(tokens.contains(r#"feature = "user_profile""#));
+    }
+}
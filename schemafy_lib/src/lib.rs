@@ -59,8 +59,8 @@ pub mod generator;
 /// This module is itself generated from a JSON schema.
 mod schema;
 
-use std::{env, path::Path,  borrow::Cow, convert::TryFrom};
-use std::io::Write;
+use std::{env, path::{Path, PathBuf},  borrow::Cow, collections::{HashMap, HashSet}, convert::TryFrom};
+use std::io::{self, Write};
 use std::ops::Index;
 
 use inflector::Inflector;
@@ -71,7 +71,7 @@ use uriparse::{Fragment, URI};
 
 pub use schema::{Schema, SimpleTypes};
 
-pub use generator::{Generator, GeneratorBuilder};
+pub use generator::{FeatureGate, Generator, GeneratorBuilder};
 
 use proc_macro2::{Span, TokenStream};
 
@@ -155,21 +155,69 @@ fn rename_keyword(prefix: &str, s: &str) -> Option<TokenStream> {
     }
 }
 
-fn field(s: &str) -> TokenStream {
-    if let Some(t) = rename_keyword("pub", s) {
-        return t;
+/// The name of the enum variant generated for the string constant `s`, e.g.
+/// `InProgress` for `"in-progress"`. A name which would start with a digit
+/// gets a `V` prefix, and the empty string becomes `Empty`.
+fn variant_ident(s: &str) -> syn::Ident {
+    let name = replace_invalid_identifier_chars(&s.to_pascal_case());
+    let name = match name.chars().next() {
+        None => "Empty".to_string(),
+        Some(c) if c.is_numeric() => format!("V{}", name),
+        Some(_) if name == "Self" => "Self_".to_string(),
+        Some(_) => name,
+    };
+    syn::Ident::new(&name, Span::call_site())
+}
+
+/// The names of the variants generated for the string values of an enum
+/// without `enumNames`, in order. Values such as `a-b` and `a_b` end up with
+/// the same name, so the later ones get a `_` appended.
+fn string_variant_idents(values: &[&str]) -> Vec<syn::Ident> {
+    let mut seen = HashSet::new();
+    values
+        .iter()
+        .map(|v| {
+            let mut variant_name = variant_ident(v);
+            while !seen.insert(variant_name.to_string()) {
+                variant_name = format_ident!("{}_", variant_name);
+            }
+            variant_name
+        })
+        .collect()
+}
+
+/// The string values of `typ` if it is an inline string enum, which gets
+/// an enum type of its own.
+fn inline_string_enum(typ: &Schema) -> Option<Vec<&str>> {
+    let is_combined =
+        |schemas: &Option<Vec<Schema>>| schemas.as_ref().map_or(false, |s| s.len() >= 2);
+    if typ.ref_.is_some()
+        || is_combined(&typ.any_of)
+        || is_combined(&typ.one_of)
+        || typ.type_ != [SimpleTypes::String]
+    {
+        return None;
     }
-    let snake = s.to_snake_case();
-    if snake == s && !snake.contains(|c: char| c == '$' || c == '#') {
-        let field = syn::Ident::new(s, Span::call_site());
-        return quote!( pub #field );
+    let values = typ.enum_.as_ref().filter(|values| !values.is_empty())?;
+    values.iter().map(Value::as_str).collect()
+}
+
+/// The variant of the enum generated for the inline string enum `typ` which
+/// `value` deserializes to.
+fn inline_enum_variant(typ: &Schema, value: &Value) -> Option<syn::Ident> {
+    let values = inline_string_enum(typ)?;
+    let index = values.iter().position(|v| Some(*v) == value.as_str())?;
+    match typ.enum_names.as_ref().filter(|names| !names.is_empty()) {
+        Some(names) => Some(str_to_ident(&names.get(index)?.to_pascal_case())),
+        None => string_variant_idents(&values).into_iter().nth(index),
     }
+}
 
-    let field = if snake.is_empty() {
-        syn::Ident::new("underscore", Span::call_site())
-    } else {
-        str_to_ident(&snake)
-    };
+fn field(s: &str, case: FieldCase) -> TokenStream {
+    let field = field_ident(s, case);
+    if field == s {
+        return quote!( pub #field );
+    }
 
     quote! {
         #[serde(rename = #s)]
@@ -177,6 +225,79 @@ fn field(s: &str) -> TokenStream {
     }
 }
 
+/// The name of the struct field generated for the property `s`: the key in
+/// the configured case, with invalid characters and keywords escaped.
+fn field_ident(s: &str, case: FieldCase) -> syn::Ident {
+    let cased = match case {
+        // Keeps the names snake case had before other cases were added, which
+        // escape keys such as `$ref` without converting them.
+        FieldCase::Snake if str_to_ident(s) != s => return str_to_ident(s),
+        _ => case.apply(s),
+    };
+    if cased.is_empty() && !s.is_empty() {
+        syn::Ident::new("underscore", Span::call_site())
+    } else {
+        str_to_ident(&cased)
+    }
+}
+
+/// The visibility of generated items.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    /// `pub`
+    #[default]
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// No visibility, i.e. private to the module the code is generated into.
+    Private,
+}
+
+impl Visibility {
+    fn tokens(self) -> TokenStream {
+        match self {
+            Visibility::Public => quote! { pub },
+            Visibility::Crate => quote! { pub(crate) },
+            Visibility::Private => TokenStream::new(),
+        }
+    }
+}
+
+/// The casing of generated struct field names. Schema keys which do not
+/// already match it get converted, with `#[serde(rename)]` keeping the
+/// original key on the wire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldCase {
+    /// `user_name`, the Rust convention.
+    #[default]
+    Snake,
+    /// `userName`.
+    Camel,
+    /// `UserName`.
+    Pascal,
+}
+
+impl FieldCase {
+    fn apply(self, s: &str) -> String {
+        match self {
+            FieldCase::Snake => s.to_snake_case(),
+            FieldCase::Camel => s.to_camel_case(),
+            FieldCase::Pascal => s.to_pascal_case(),
+        }
+    }
+}
+
+/// A struct field as needed to generate a setter for it in the builder.
+struct BuilderField {
+    ident: syn::Ident,
+    /// The type the setter accepts; the inner type for `Option` fields.
+    typ: TokenStream,
+    optional: bool,
+    /// The value used when the setter was not called, or `None` if the
+    /// field must be set.
+    missing: Option<TokenStream>,
+}
+
 fn merge_option<T, F>(mut result: &mut Option<T>, r: &Option<T>, f: F)
     where
         F: FnOnce(&mut T, &T),
@@ -261,8 +382,14 @@ struct FieldExpander<'a, 'r: 'a> {
 }
 
 impl<'a, 'r> FieldExpander<'a, 'r> {
-    fn expand_fields(&mut self, type_name: &str, schema: &Schema) -> Vec<TokenStream> {
+    fn expand_fields(
+        &mut self,
+        type_name: &str,
+        schema: &Schema,
+    ) -> (Vec<TokenStream>, Vec<BuilderField>) {
         let schema = self.expander.schema(schema);
+        let mut builder_fields = Vec::new();
+        let field_case = self.expander.field_case;
         let mut tokens : Vec<TokenStream> =
             schema.properties
                 .iter()
@@ -279,8 +406,9 @@ impl<'a, 'r> FieldExpander<'a, 'r> {
                 .filter(|(_, field_type, _)| (!field_type.typ.starts_with("Option<") || field_type.has_custom_type))
                 .map(|(field_name, field_type, value)| {
 
-                    let key = field(field_name);
+                    let key = field(field_name, field_case);
                     let typ = field_type.typ.parse::<TokenStream>().unwrap();
+                    builder_fields.push(builder_field(field_name, &field_type, field_case));
 
                     let default = if field_type.default {
                         Some(quote! { #[serde(default)] })
@@ -317,10 +445,43 @@ impl<'a, 'r> FieldExpander<'a, 'r> {
                     #[serde(flatten)]
                     pub property: ::std::collections::HashMap<String, YamlValue>,
                 }
-            )
+            );
+            builder_fields.push(BuilderField {
+                ident: syn::Ident::new("property", Span::call_site()),
+                typ: quote! { ::std::collections::HashMap<String, YamlValue> },
+                optional: false,
+                missing: Some(quote! { Default::default() }),
+            });
         }
 
-        tokens
+        (tokens, builder_fields)
+    }
+}
+
+fn builder_field(field_name: &str, field_type: &FieldType, case: FieldCase) -> BuilderField {
+    let inner = field_type
+        .typ
+        .strip_prefix("Option<")
+        .and_then(|typ| typ.strip_suffix('>'));
+    let default_fn = field_type
+        .attributes
+        .iter()
+        .find_map(|attr| attr.strip_prefix("default=\"")?.strip_suffix('"'));
+    let missing = if inner.is_some() {
+        Some(quote! { None })
+    } else if let Some(default_fn) = default_fn {
+        let default_fn = syn::Ident::new(default_fn, Span::call_site());
+        Some(quote! { #default_fn() })
+    } else if field_type.default {
+        Some(quote! { Default::default() })
+    } else {
+        None
+    };
+    BuilderField {
+        ident: field_ident(field_name, case),
+        typ: inner.unwrap_or(&field_type.typ).parse().unwrap(),
+        optional: inner.is_some(),
+        missing,
     }
 }
 
@@ -331,7 +492,24 @@ pub struct Expander<'r> {
     current_type: String,
     current_field: String,
     types: Vec<(String, TokenStream)>,
-    default_paths: Vec<(String, serde_yaml::Value)>,
+    /// The functions providing schema `default`s: their name, return type and
+    /// body.
+    default_paths: Vec<(String, String, String)>,
+    derive_default: bool,
+    derive_json_schema: bool,
+    derive_builder: bool,
+    field_case: FieldCase,
+    visibility: Visibility,
+    ref_schemas: Option<&'r HashMap<String, RefSchema>>,
+}
+
+/// A schema file referenced with `$ref` by the schema being expanded. See
+/// [`Expander::with_ref_schemas`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefSchema {
+    /// The name of the root type generated for the file.
+    pub root_name: String,
+    pub schema: Schema,
 }
 
 struct FieldType {
@@ -369,7 +547,186 @@ impl<'r> Expander<'r> {
             current_type: "".into(),
             types: Vec::new(),
             default_paths: Vec::new(),
+            derive_default: false,
+            derive_json_schema: false,
+            derive_builder: false,
+            field_case: FieldCase::Snake,
+            visibility: Visibility::Public,
+            ref_schemas: None,
+        }
+    }
+
+    /// Emit `Default` impls for the generated types.
+    ///
+    /// Structs and enums derive `Default`, with the first variant of an
+    /// enum being the default one. Untagged `oneOf` enums get a manual
+    /// impl wrapping the default of their first variant.
+    ///
+    /// `Default::default()` does not know about required fields or schema
+    /// `default` values: required fields are left empty (`""`, `0`,
+    /// `YamlValue::Null`, ...) and schema defaults are only applied by serde
+    /// when deserializing a missing field.
+    pub fn with_derive_default(mut self, derive_default: bool) -> Self {
+        self.derive_default = derive_default;
+        self
+    }
+
+    /// Emit `schemars::JsonSchema` impls for the generated types, so that a
+    /// JSON Schema can be produced from them at runtime. The generated code
+    /// then needs the `schemars` crate and the `schemars` feature of
+    /// `schemafy_core`.
+    ///
+    /// Integer enums get a manual impl listing their discriminants, as the
+    /// derive would describe them by variant name.
+    pub fn with_derive_json_schema(mut self, derive_json_schema: bool) -> Self {
+        self.derive_json_schema = derive_json_schema;
+        self
+    }
+
+    /// Emit a `<Type>Builder` for every generated struct, created with
+    /// `<Type>::builder()`. It has a chained setter per field, taking the
+    /// inner type for `Option` fields, and a `build()` which fails with a
+    /// message naming the first required field that was not set. Fields
+    /// with a schema `default` fall back to it.
+    pub fn with_derive_builder(mut self, derive_builder: bool) -> Self {
+        self.derive_builder = derive_builder;
+        self
+    }
+
+    /// Name struct fields in `field_case` instead of snake_case. Other cases
+    /// also add `#[allow(non_snake_case)]` to the generated structs.
+    pub fn with_field_case(mut self, field_case: FieldCase) -> Self {
+        self.field_case = field_case;
+        self
+    }
+
+    /// Declare the generated types, builders and their methods with
+    /// `visibility` instead of `pub`. Struct fields stay `pub`.
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Resolve `$ref`s to other schema files, such as
+    /// `common.schema.yaml#/definitions/address`, against `ref_schemas`,
+    /// which maps each file as written in the `$ref` to its parsed schema.
+    /// The types of other files are referred to by name rather than
+    /// generated, so they must be generated into the same module, as
+    /// [`compile_schemas`] does. A `$ref` to a whole file refers to the
+    /// [`root_name`](RefSchema::root_name) of that file. `$ref`s within a
+    /// referenced file, such as `#/definitions/name`, are resolved against
+    /// that file.
+    ///
+    /// [`Generator`] loads the files referenced by its input file.
+    pub fn with_ref_schemas(mut self, ref_schemas: &'r HashMap<String, RefSchema>) -> Self {
+        self.ref_schemas = Some(ref_schemas);
+        self
+    }
+
+    fn allow_field_case(&self) -> Option<TokenStream> {
+        if self.field_case == FieldCase::Snake {
+            None
+        } else {
+            Some(quote! { #[allow(non_snake_case)] })
+        }
+    }
+
+    fn builder_impl(&self, name: &syn::Ident, fields: &[BuilderField]) -> Option<TokenStream> {
+        if !self.derive_builder {
+            return None;
         }
+        let builder = syn::Ident::new(&format!("{}Builder", name), Span::call_site());
+        let vis = self.visibility.tokens();
+        let idents: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+        let types: Vec<_> = fields.iter().map(|field| &field.typ).collect();
+        let setters = fields.iter().map(|field| {
+            let (ident, typ) = (&field.ident, &field.typ);
+            let doc = if field.optional {
+                format!(" Sets `{}` to `Some(value)`.", ident)
+            } else {
+                format!(" Sets `{}`.", ident)
+            };
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(mut self, value: impl Into<#typ>) -> Self {
+                    self.#ident = Some(value.into());
+                    self
+                }
+            }
+        });
+        let values = fields.iter().map(|field| {
+            let ident = &field.ident;
+            match &field.missing {
+                Some(_) if field.optional => quote! { self.#ident },
+                Some(missing) => quote! { self.#ident.unwrap_or_else(|| #missing) },
+                None => {
+                    let message = format!("missing required field `{}`", ident);
+                    quote! { self.#ident.ok_or(#message)? }
+                }
+            }
+        });
+        let doc = format!(" A builder for [`{}`].", name);
+        let allow_field_case = self.allow_field_case();
+        Some(quote! {
+            #[doc = #doc]
+            #[derive(Clone, Debug, Default)]
+            #allow_field_case
+            #vis struct #builder {
+                #(#idents: Option<#types>,)*
+            }
+
+            impl #name {
+                #vis fn builder() -> #builder {
+                    #builder::default()
+                }
+            }
+
+            #allow_field_case
+            impl #builder {
+                #(#setters)*
+
+                #vis fn build(self) -> Result<#name, String> {
+                    Ok(#name {
+                        #(#idents: #values,)*
+                    })
+                }
+            }
+        })
+    }
+
+    fn json_schema_derive(&self) -> Option<TokenStream> {
+        if self.derive_json_schema {
+            Some(quote! { #[derive(schemars::JsonSchema)] })
+        } else {
+            None
+        }
+    }
+
+    fn json_schema_repr_impl(
+        &self,
+        name: &syn::Ident,
+        schema_name: &str,
+        values: &[syn::LitInt],
+    ) -> Option<TokenStream> {
+        if !self.derive_json_schema {
+            return None;
+        }
+        Some(quote! {
+            impl schemars::JsonSchema for #name {
+                fn schema_name() -> String {
+                    #schema_name.to_owned()
+                }
+
+                fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+                    schemars::schema::SchemaObject {
+                        instance_type: Some(schemars::schema::InstanceType::Integer.into()),
+                        enum_values: Some(vec![#(schemars::_serde_json::Value::from(#values as i64)),*]),
+                        ..Default::default()
+                    }
+                    .into()
+                }
+            }
+        })
     }
 
     fn type_ref(&self, s: &str) -> String {
@@ -385,7 +742,12 @@ impl<'r> Expander<'r> {
             .map(|fragment| fragment.to_string())
             .unwrap_or_else(|| s.to_owned());
 
-        let ref_ = if fragment.is_empty() {
+        let file = ref_file(s);
+        let ref_ = if !file.is_empty() && s[file.len()..].trim_start_matches('#').is_empty() {
+            self.ref_schemas
+                .and_then(|schemas| schemas.get(file))
+                .map_or_else(|| ref_root_name(file), |ref_schema| &ref_schema.root_name)
+        } else if fragment.is_empty() {
             self.root_name.expect("No root name specified for schema")
         } else {
             fragment.split('/').last().expect("Component")
@@ -397,17 +759,23 @@ impl<'r> Expander<'r> {
     }
 
     fn schema(&self, schema: &'r Schema) -> Cow<'r, Schema> {
-        let schema = match schema.ref_ {
-            Some(ref ref_) => self.schema_ref(ref_),
-            None => schema,
+        self.schema_in(self.root, schema)
+    }
+
+    /// Like [`schema`](Self::schema), for a schema from the file whose root
+    /// is `root`, against which its local `$ref`s are resolved.
+    fn schema_in(&self, root: &'r Schema, schema: &'r Schema) -> Cow<'r, Schema> {
+        let (root, schema) = match schema.ref_ {
+            Some(ref ref_) => self.schema_ref(root, ref_),
+            None => (root, schema),
         };
         match schema.all_of {
             Some(ref all_of) if !all_of.is_empty() => {
                 all_of
                     .iter()
                     .skip(1)
-                    .fold(self.schema(&all_of[0]).clone(), |mut result, def| {
-                        merge_all_of(result.to_mut(), &self.schema(def));
+                    .fold(self.schema_in(root, &all_of[0]).clone(), |mut result, def| {
+                        merge_all_of(result.to_mut(), &self.schema_in(root, def));
                         result
                     })
             }
@@ -415,10 +783,31 @@ impl<'r> Expander<'r> {
         }
     }
 
-    fn schema_ref(&self, s: &str) -> &'r Schema {
-        s.split('/').fold(self.root, |schema, comp| {
+    /// Resolves `s` from the file whose root is `root`, returning the root of
+    /// the file the referenced schema is in along with the schema.
+    fn schema_ref(&self, root: &'r Schema, s: &str) -> (&'r Schema, &'r Schema) {
+        let file = ref_file(s);
+        if !file.is_empty() {
+            let root = &self
+                .ref_schemas
+                .and_then(|schemas| schemas.get(file))
+                .unwrap_or_else(|| panic!("Unresolved schema file: `{}`", s))
+                .schema;
+            let schema = s[file.len()..]
+                .trim_start_matches('#')
+                .split('/')
+                .filter(|comp| !comp.is_empty() && *comp != "definitions")
+                .fold(root, |schema, comp| {
+                    schema
+                        .definitions
+                        .get(comp)
+                        .unwrap_or_else(|| panic!("Expected definition: `{}` {}", s, comp))
+                });
+            return (root, schema);
+        }
+        let schema = s.split('/').fold(root, |schema, comp| {
             if comp.ends_with('#') {
-                self.root
+                root
             } else if comp == "definitions" {
                 schema
             } else {
@@ -427,7 +816,8 @@ impl<'r> Expander<'r> {
                     .get(comp)
                     .unwrap_or_else(|| panic!("Expected definition: `{}` {}", s, comp))
             }
-        })
+        });
+        (root, schema)
     }
 
     fn expand_type(&mut self, type_name: &str, required: bool, typ: &Schema) -> FieldType {
@@ -443,7 +833,16 @@ impl<'r> Expander<'r> {
 
                 let default_path = format!("default_{}{}", current_type, self.current_field).to_snake_case();
                 result.attributes.push(format!("default=\"{}\"", default_path));
-                self.default_paths.push((default_path, defualt_value.clone()));
+                let (type_str, value_str) = match inline_enum_variant(typ, defualt_value) {
+                    Some(variant) => (result.typ.clone(), format!("{}::{}", result.typ, variant)),
+                    None => match defualt_value {
+                        Value::String(value) => ("String".into(), format!("\"{}\".to_string()", value)),
+                        Value::Number(value) => ("i64".into(), value.to_string()),
+                        Value::Bool(value) => ("bool".into(), value.to_string()),
+                        _ => panic!("default value has invalid type!")
+                    },
+                };
+                self.default_paths.push((default_path, type_str, value_str));
 
             } else {
                 if !result.default {
@@ -511,13 +910,23 @@ impl<'r> Expander<'r> {
             fields.retain(|property, _ | typ.required.as_ref().map(|e| e.contains(property)).unwrap_or_default());
 
             match typ.type_[0] {
-                SimpleTypes::String => {
-                    if typ.enum_.as_ref().map_or(false, |e| e.is_empty()) {
-                        "YamlValue".into()
-                    } else {
-                        "String".into()
+                SimpleTypes::String => match &typ.enum_ {
+                    Some(values) if values.is_empty() => "YamlValue".into(),
+                    // String constants defined inline get an enum of their own
+                    Some(_) if inline_string_enum(typ).is_some() => {
+                        let name = self.inline_type_name();
+                        let tokens = self.expand_schema(&name, typ);
+                        self.types.push((name.clone(), tokens));
+
+                        FieldType {
+                            typ: name,
+                            attributes: Vec::new(),
+                            default: false,
+                            has_custom_type: true,
+                        }
                     }
-                }
+                    _ => "String".into(),
+                },
                 SimpleTypes::Integer => "i64".into(),
                 SimpleTypes::Boolean => "bool".into(),
                 SimpleTypes::Number => "f64".into(),
@@ -525,15 +934,7 @@ impl<'r> Expander<'r> {
                 SimpleTypes::Object
                 if !fields.is_empty() =>
                     {
-                        let name;
-                        if self.current_type.strip_suffix(format!("{}Item", self.current_field).as_str()).is_some() {
-
-                            name = self.current_type.to_pascal_case();
-                            self.current_type = name.clone();
-                        } else {
-                            name = format!("{}{}", self.current_type, self.current_field).to_pascal_case();
-                        }
-
+                        let name = self.inline_type_name();
                         let tokens = self.expand_schema(&name, typ);
                         self.types.push((name.clone(), tokens));
 
@@ -572,6 +973,18 @@ impl<'r> Expander<'r> {
         }
     }
 
+    /// The name of the type generated for a schema defined inline, in the
+    /// current field or in the items of the current array field.
+    fn inline_type_name(&mut self) -> String {
+        if self.current_type.strip_suffix(format!("{}Item", self.current_field).as_str()).is_some() {
+            let name = self.current_type.to_pascal_case();
+            self.current_type = name.clone();
+            name
+        } else {
+            format!("{}{}", self.current_type, self.current_field).to_pascal_case()
+        }
+    }
+
     fn expand_one_of(&mut self, schemas: &[Schema]) -> (String, TokenStream) {
         let current_field = if self.current_field.is_empty() {
             "".to_owned()
@@ -601,12 +1014,28 @@ impl<'r> Expander<'r> {
             })
             .unzip();
         let type_name_ident = syn::Ident::new(&saved_type, Span::call_site());
+        let default_impl = if self.derive_default {
+            let first_variant = &variant_names[0];
+            Some(quote! {
+                impl Default for #type_name_ident {
+                    fn default() -> Self {
+                        #type_name_ident::#first_variant(Default::default())
+                    }
+                }
+            })
+        } else {
+            None
+        };
+        let json_schema = self.json_schema_derive();
+        let vis = self.visibility.tokens();
         let type_def = quote! {
             #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+            #json_schema
             #[serde(untagged)]
-            pub enum #type_name_ident {
+            #vis enum #type_name_ident {
                 #(#variant_names(#variant_types)),*
             }
+            #default_impl
         };
         (saved_type, type_def)
     }
@@ -630,6 +1059,7 @@ impl<'r> Expander<'r> {
 
     fn expand_schema(&mut self, original_name: &str, schema: &Schema) -> TokenStream {
         self.expand_definitions(schema);
+        let vis = self.visibility.tokens();
 
         let pascal_case_name = replace_invalid_identifier_chars(&original_name.to_pascal_case());
         self.current_type.clone_from(&pascal_case_name);
@@ -640,6 +1070,7 @@ impl<'r> Expander<'r> {
 
             field_expander.expand_fields(original_name, schema)
         };
+        let (fields, builder_fields) = fields;
         let name = syn::Ident::new(&pascal_case_name, Span::call_site());
         let is_struct = !fields.is_empty();
 
@@ -652,14 +1083,27 @@ impl<'r> Expander<'r> {
         };
         let is_enum = schema.enum_.as_ref().map_or(false, |e| !e.is_empty());
         let type_decl = if is_struct {
+            let derive_default = if self.derive_default {
+                Some(quote! { #[derive(Default)] })
+            } else {
+                None
+            };
+            let json_schema = self.json_schema_derive();
+            let allow_field_case = self.allow_field_case();
             let mut token  =
                 quote! {
                     #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+                    #derive_default
+                    #json_schema
                     #serde_rename
-                    pub struct #name {
+                    #allow_field_case
+                    #vis struct #name {
                         #(#fields),*
                     }
                 };
+            if let Some(builder) = self.builder_impl(&name, &builder_fields) {
+                token.extend(builder);
+            }
 
             let mut option_key = None;
             if let Some(array) = &schema.required {
@@ -671,11 +1115,7 @@ impl<'r> Expander<'r> {
                 let property = schema.properties.index(&key);
                 if property.type_.contains(&SimpleTypes::Integer) {
 
-                    let mut key_token = TokenStream::new();
-                    if let Ok(token) = key.to_snake_case().parse() {
-                        key_token = token;
-                    }
-
+                    let key_token = field_ident(&key, self.field_case);
                     let identifier = quote! {
                         impl Identifier for #name {
                             fn key(&self) -> i64 {
@@ -687,14 +1127,7 @@ impl<'r> Expander<'r> {
                 }
             };
 
-            for (path, value) in &self.default_paths {
-                let (type_str, value_str) = match value {
-                    Value::String(value) => ("String", format!("\"{}\".to_string()", value)),
-                    Value::Number(value) => ("i64".into(), value.to_string()),
-                    Value::Bool(value) => ("bool", value.to_string()),
-                    _ => panic!("default value has invalid type!")
-                };
-
+            for (path, type_str, value_str) in &self.default_paths {
                 let path_token : TokenStream = path.parse().unwrap();
                 let type_token : TokenStream = type_str.parse().unwrap();
                 let value_token : TokenStream = value_str.parse().unwrap();
@@ -713,7 +1146,8 @@ impl<'r> Expander<'r> {
         } else if is_enum {
             let mut optional = false;
             let mut repr_i64 = false;
-            let variants = if schema.enum_names.as_ref().map_or(false, |e| !e.is_empty()) {
+            let mut repr_values = Vec::new();
+            let mut variants = if schema.enum_names.as_ref().map_or(false, |e| !e.is_empty()) {
                 let values = schema.enum_.as_ref().map_or(&[][..], |v| v);
                 let names = schema.enum_names.as_ref().map_or(&[][..], |v| v);
                 if names.len() != values.len() {
@@ -742,6 +1176,7 @@ impl<'r> Expander<'r> {
                             Value::Number(ref n) => {
                                 repr_i64 = true;
                                 let num = syn::LitInt::new(&n.to_string(), Span::call_site());
+                                repr_values.push(num.clone());
                                 Some(quote! {
                                     #variant_name = #num
                                 })
@@ -755,73 +1190,92 @@ impl<'r> Expander<'r> {
                     })
                     .collect::<Vec<_>>()
             } else {
-                schema
+                let values = schema
                     .enum_
                     .as_ref()
                     .map_or(&[][..], |v| v)
                     .iter()
                     .flat_map(|v| match *v {
-                        Value::String(ref v) => {
-                            let pascal_case_variant = v.to_pascal_case();
-                            let variant_name = rename_keyword("", &pascal_case_variant)
-                                .unwrap_or_else(|| {
-                                    let v =
-                                        syn::Ident::new(&pascal_case_variant, Span::call_site());
-                                    quote!(#v)
-                                });
-                            Some(if pascal_case_variant == *v {
-                                variant_name
-                            } else {
-                                quote! {
-                                    #[serde(rename = #v)]
-                                    #variant_name
-                                }
-                            })
-                        }
+                        Value::String(ref v) => Some(v.as_str()),
                         Value::Null => {
                             optional = true;
                             None
                         }
                         _ => panic!("Expected string for enum got `{}`", v.as_str().unwrap()),
                     })
+                    .collect::<Vec<_>>();
+                values
+                    .iter()
+                    .zip(string_variant_idents(&values))
+                    .map(|(v, variant_name)| {
+                        if variant_name == v {
+                            quote!(#variant_name)
+                        } else {
+                            quote! {
+                                #[serde(rename = #v)]
+                                #variant_name
+                            }
+                        }
+                    })
                     .collect::<Vec<_>>()
             };
+            let derive_default = match variants.first_mut() {
+                Some(first) if self.derive_default => {
+                    *first = quote! {
+                        #[default]
+                        #first
+                    };
+                    Some(quote! { #[derive(Default)] })
+                }
+                _ => None,
+            };
+            let json_schema = self.json_schema_derive();
             if optional {
                 let enum_name = syn::Ident::new(&format!("{}_", name), Span::call_site());
                 if repr_i64 {
+                    let json_schema_impl = self.json_schema_repr_impl(&enum_name, original_name, &repr_values);
                     quote! {
-                        pub type #name = Option<#enum_name>;
+                        #vis type #name = Option<#enum_name>;
                         #[derive(Clone, PartialEq, Debug, Serialize_repr, Deserialize_repr)]
+                        #derive_default
                         #serde_rename
                         #[repr(i64)]
-                        pub enum #enum_name {
+                        #vis enum #enum_name {
                             #(#variants),*
                         }
+                        #json_schema_impl
                     }
                 } else {
                     quote! {
-                        pub type #name = Option<#enum_name>;
+                        #vis type #name = Option<#enum_name>;
                         #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+                        #derive_default
+                        #json_schema
                         #serde_rename
-                        pub enum #enum_name {
+                        #vis enum #enum_name {
                             #(#variants),*
                         }
                     }
                 }
             } else if repr_i64 {
+                let json_schema_impl = self.json_schema_repr_impl(&name, original_name, &repr_values);
                 quote! {
                     #[derive(Clone, PartialEq, Debug, Serialize_repr, Deserialize_repr)]
+                    #derive_default
                     #serde_rename
                     #[repr(i64)]
-                    pub enum #name {
+                    #vis enum #name {
                         #(#variants),*
                     }
+                    #json_schema_impl
                 }
             } else {
                 quote! {
                     #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+                    #derive_default
+                    #json_schema
                     #serde_rename
-                    pub enum #name {
+                    #vis enum #name {
                         #(#variants),*
                     }
                 }
@@ -838,7 +1292,7 @@ impl<'r> Expander<'r> {
             }
             return quote! {
                 #[allow(dead_code)]
-                pub type #name = #typ;
+                #vis type #name = #typ;
             };
         };
         type_decl
@@ -865,38 +1319,207 @@ impl<'r> Expander<'r> {
     }
 }
 
+/// Returns the file part of a `$ref`, e.g. `common.schema.yaml` for
+/// `common.schema.yaml#/definitions/address`, or `""` for a reference within
+/// the same schema. Without a `#`, only a name with an extension is taken as
+/// a file. References with a URL scheme are treated as local, as they cannot
+/// be loaded.
+pub(crate) fn ref_file(s: &str) -> &str {
+    let file = s.split('#').next().unwrap();
+    if file.contains("://") || (file == s && !file.contains('.')) {
+        ""
+    } else {
+        file
+    }
+}
+
+/// The default root type name for the schema file `file`, as written in a
+/// `$ref`: its file name without the extension and a `.schema` suffix, so
+/// `user.v2.schema.yaml` becomes `user.v2`, like [`schema_base_name`] for
+/// `*.schema.yaml` files.
+pub(crate) fn ref_root_name(file: &str) -> &str {
+    let name = file.rsplit('/').next().unwrap();
+    let name = name.rfind('.').map_or(name, |dot| &name[..dot]);
+    name.strip_suffix(".schema").unwrap_or(name)
+}
+
 pub fn compile_schemas(input_dir: &str) {
-    let input_dir = Path::new(input_dir);
-    let input_parent_dir = input_dir.parent().unwrap();
+    compile_schemas_with(input_dir, |_, builder| builder)
+}
+
+/// Like [`compile_schemas`], but lets `configure` adjust the
+/// [`GeneratorBuilder`] used for each schema file, e.g. to enable
+/// [`GeneratorBuilder::with_derive_default`].
+pub fn compile_schemas_with<F>(input_dir: &str, configure: F)
+    where
+        F: for<'a, 'b> Fn(&Path, GeneratorBuilder<'a, 'b>) -> GeneratorBuilder<'a, 'b> + Sync,
+{
+    let suffix = schema_suffix(Path::new(input_dir)).to_owned();
+    compile_schemas_named(input_dir, |path| schema_base_name(path, &suffix), configure)
+}
+
+/// Like [`compile_schemas_with`], but lets `base_name` choose the root type
+/// name for each schema file from its full path, instead of the default
+/// [`schema_base_name`].
+///
+/// With the `rayon` feature the schema files are generated in parallel; the
+/// output is the same as when generating them one after another.
+///
+/// # Panics
+///
+/// Panics if two schema files map to the same base name.
+pub fn compile_schemas_named<N, F>(input_dir: &str, base_name: N, configure: F)
+    where
+        N: Fn(&Path) -> String,
+        F: for<'a, 'b> Fn(&Path, GeneratorBuilder<'a, 'b>) -> GeneratorBuilder<'a, 'b> + Sync,
+{
+    let mut seen = HashMap::new();
+    let mut schemas = Vec::new();
+    for input_path in schema_files(Path::new(input_dir)).unwrap_or_default() {
+        let prefix_name = base_name(&input_path);
+        if let Some(other) = seen.insert(prefix_name.clone(), input_path.clone()) {
+            panic!(
+                "`{}` and `{}` both map to the base name `{}`",
+                other.display(),
+                input_path.display(),
+                prefix_name
+            );
+        }
+        schemas.push((input_path, prefix_name));
+    }
+
+    let out_string = generate_schemas(&schemas, &configure, cfg!(feature = "rayon")).unwrap();
+    write_resource(&out_string);
+}
+
+/// Generates the code for each `(input_path, root_name)` pair and joins it in
+/// order. Every file gets its own [`Generator`], so files can be generated
+/// on separate threads when `parallel` is set and the `rayon` feature is
+/// enabled.
+///
+/// If any generator is configured with a provenance header, a single header
+/// listing every schema file is put at the top.
+fn generate_schemas<F>(
+    schemas: &[(PathBuf, String)],
+    configure: &F,
+    parallel: bool,
+) -> io::Result<String>
+    where
+        F: for<'a, 'b> Fn(&Path, GeneratorBuilder<'a, 'b>) -> GeneratorBuilder<'a, 'b> + Sync,
+{
+    let root_names: HashMap<PathBuf, String> = schemas.iter().cloned().collect();
+    let generate = |(input_path, prefix_name): &(PathBuf, String)| {
+        let builder = Generator::builder()
+            .with_root_name_str(prefix_name)
+            .with_input_file(input_path)
+            .with_ref_root_names(root_names.clone());
+        let generator = configure(input_path, builder).build();
+        let mut out = String::new();
+        generator
+            .append_to_string(&mut out)
+            .map(|()| (generator.provenance_header, out))
+    };
+
+    #[cfg(feature = "rayon")]
+    let generated: Vec<io::Result<(bool, String)>> = if parallel {
+        use rayon::prelude::*;
+        schemas.par_iter().map(generate).collect()
+    } else {
+        schemas.iter().map(generate).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let generated: Vec<io::Result<(bool, String)>> = {
+        let _ = parallel;
+        schemas.iter().map(generate).collect()
+    };
+
+    let mut out_string = quote! {
+        pub trait Identifier {
+            fn key(&self) -> i64;
+        }
+    }.to_string();
+    let mut provenance_header = false;
+    for out in generated {
+        let (header, out) = out?;
+        provenance_header |= header;
+        out_string.push_str(&out);
+    }
+    if provenance_header {
+        let sources: Vec<_> = schemas.iter().map(|(path, _)| path.as_path()).collect();
+        out_string.insert_str(0, &generator::combined_provenance_header(&sources));
+    }
+    Ok(out_string)
+}
+
+/// Reports what [`compile_schemas`] would do for `input_path` without
+/// generating anything: the schema files it picks up, each paired with the
+/// file its types would be written to. Fails with `NotFound` if no ancestor
+/// of the current directory contains the schema directory.
+pub fn compile_schemas_dry_run(input_path: &Path) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let output_path = resource_path();
+    Ok(schema_files(input_path)?
+        .into_iter()
+        .map(|input_path| (input_path, output_path.clone()))
+        .collect())
+}
+
+/// Returns the default base name for the schema file at `path`: its file
+/// name with only `suffix` removed, so `user.v2.schema.yaml` becomes
+/// `user.v2` rather than `user`.
+pub fn schema_base_name(path: &Path, suffix: &str) -> String {
+    let file_name = path.file_name().unwrap().to_str().unwrap();
+    file_name.strip_suffix(suffix).unwrap_or(file_name).to_owned()
+}
+
+/// Returns the file name suffix selected by a pattern such as
+/// `schemas/*.schema.yaml`. Without a leading `*`, everything from the first
+/// dot is used.
+fn schema_suffix(input_dir: &Path) -> &str {
     let input_file_name = input_dir.file_name().unwrap().to_str().unwrap();
+    match input_file_name.strip_prefix('*') {
+        Some(suffix) => suffix,
+        None => &input_file_name[input_file_name.find('.').unwrap()..],
+    }
+}
 
-    let first_dot_pos = input_file_name.find('.').unwrap();
-    let input_file_suffix = &input_file_name[first_dot_pos..];
+/// Lists the schema files selected by `input_dir`, sorted by path. The
+/// directory is looked up relative to the closest ancestor of the current
+/// directory that contains it.
+fn schema_files(input_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let input_parent_dir = input_dir.parent().unwrap();
+    let input_file_suffix = schema_suffix(input_dir);
 
-    let current_path = env::current_dir().unwrap();
-    let filtered: Vec<_> = current_path.ancestors()
+    let current_path = env::current_dir()?;
+    let schema_dir = current_path.ancestors()
         .map(|path| path.join(input_parent_dir))
-        .filter(|path| path.exists())
-        .filter_map(|path| path.read_dir().ok())
-        .take(1)
-        .flatten()
+        .find(|path| path.exists())
+        .ok_or_else(|| {
+            let message = format!("`{}` not found", input_parent_dir.display());
+            io::Error::new(io::ErrorKind::NotFound, message)
+        })?;
+    let mut files: Vec<_> = schema_dir.read_dir()?
         .filter_map(|entry| entry.ok())
-        .filter(|path| path.file_name().to_str().unwrap().contains(input_file_suffix))
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_str().unwrap();
+            file_name.len() > input_file_suffix.len() && file_name.ends_with(input_file_suffix)
+        })
+        .map(|entry| entry.path())
         .collect();
+    files.sort();
+    Ok(files)
+}
 
-    let path_str;
-    let output_path = match env::var("OUT_DIR") {
-        Ok(path) => {
-            path_str = path;
-            Path::new(&path_str)
-        }
-        Err(_) => current_path.as_path(),
-    };
-    let output_file_name = output_path.join("resource.rs");
-
-    if let Ok(mut old_file) = std::fs::OpenOptions::new().write(true).open(&output_file_name) {
-        old_file.flush().unwrap();
-    }
+/// Like [`compile_schemas`], but reads all schemas from the single
+/// multi-document YAML file `input_file`, with documents separated by `---`.
+/// Each document's root type is named after its `name_field` (e.g. `title`).
+/// See [`Generator::generate_documents`].
+pub fn compile_schema_documents(input_file: &str, name_field: &str) {
+    let current_path = env::current_dir().unwrap();
+    let input_path = current_path.ancestors()
+        .map(|path| path.join(input_file))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| panic!("Unable to find `{}`", input_file));
 
     let mut out_string = String::new();
     out_string.push_str(&quote! {
@@ -905,19 +1528,37 @@ pub fn compile_schemas(input_dir: &str) {
         }
     }.to_string());
 
-    for entry in filtered {
-        let input_file_name = entry.file_name().into_string().unwrap();
-        let prefix_name = input_file_name.strip_suffix(input_file_suffix).unwrap();
-
-        Generator::builder()
-            .with_root_name_str(&prefix_name)
-            .with_input_file(&entry.path())
+    out_string.push_str(
+        &Generator::builder()
+            .with_input_file(&input_path)
             .build()
-            .append_to_string(&mut out_string)
-            .unwrap();
+            .generate_documents(name_field)
+            .to_string(),
+    );
+
+    write_resource(&out_string);
+}
+
+/// Returns the file generated code is written to: `resource.rs` in
+/// `OUT_DIR`, or in the current directory outside of build scripts.
+fn resource_path() -> PathBuf {
+    let output_path = match env::var("OUT_DIR") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => env::current_dir().unwrap(),
+    };
+    output_path.join("resource.rs")
+}
+
+/// Writes the generated code to `resource.rs` in `OUT_DIR`, or in the current
+/// directory outside of a build script, and formats it.
+fn write_resource(out_string: &str) {
+    let output_file_name = resource_path();
+
+    if let Ok(mut old_file) = std::fs::OpenOptions::new().write(true).open(&output_file_name) {
+        old_file.flush().unwrap();
     }
 
-    std::fs::write(&output_file_name, &out_string).unwrap();
+    std::fs::write(&output_file_name, out_string).unwrap();
     std::process::Command::new("rustfmt")
         .arg(&output_file_name.as_os_str())
         .output().unwrap();
@@ -925,22 +1566,8 @@ pub fn compile_schemas(input_dir: &str) {
 
 pub fn compile(out_dir: &str, input_dir: &str) {
     let input_dir = Path::new(input_dir);
-    let input_parent_dir = input_dir.parent().unwrap();
-    let input_file_name = input_dir.file_name().unwrap().to_str().unwrap();
-
-    let first_dot_pos = input_file_name.find('.').unwrap();
-    let input_file_suffix = &input_file_name[first_dot_pos..];
-
-    let current_path = env::current_dir().unwrap();
-    let filtered: Vec<_> = current_path.ancestors()
-        .map(|path| path.join(input_parent_dir))
-        .filter(|path| path.exists())
-        .filter_map(|path| path.read_dir().ok())
-        .take(1)
-        .flatten()
-        .filter_map(|entry| entry.ok())
-        .filter(|path| path.file_name().to_str().unwrap().contains(input_file_suffix))
-        .collect();
+    let input_file_suffix = schema_suffix(input_dir);
+    let filtered = schema_files(input_dir).unwrap_or_default();
 
     let output_path = Path::new(out_dir);
     let output_file_name = output_path.join("resource.rs");
@@ -959,13 +1586,12 @@ pub fn compile(out_dir: &str, input_dir: &str) {
         }
     }.to_string());
 
-    for entry in filtered {
-        let input_file_name = entry.file_name().into_string().unwrap();
-        let prefix_name = input_file_name.strip_suffix(input_file_suffix).unwrap();
+    for input_path in filtered {
+        let prefix_name = schema_base_name(&input_path, input_file_suffix);
 
         Generator::builder()
             .with_root_name_str(&prefix_name)
-            .with_input_file(&entry.path())
+            .with_input_file(&input_path)
             .build()
             .append_to_string(&mut out_string)
             .unwrap();
@@ -1008,6 +1634,13 @@ mod tests {
             "Path"
         );
         assert_eq!(expander.type_ref("#/only/Fragment"), "Fragment");
+        assert_eq!(expander.type_ref("common.schema.yaml"), "Common");
+        assert_eq!(expander.type_ref("../common.schema.yaml#"), "Common");
+        assert_eq!(expander.type_ref("user.v2.schema.yaml"), "UserV2");
+        assert_eq!(
+            expander.type_ref("common.schema.yaml#/definitions/address"),
+            "Address"
+        );
 
         // Invalid cases, just to verify the behavior
         assert_eq!(expander.type_ref("ref"), "Ref");
@@ -1040,4 +1673,86 @@ mod tests {
         assert!(types.contains("RootKM"));
         assert!(types.contains("RootTV"));
     }
+
+    #[test]
+    fn dotted_schema_file_names() {
+        let input_dir = Path::new("tests/dotted/*.schema.yaml");
+        let names: Vec<_> = schema_files(input_dir)
+            .unwrap()
+            .iter()
+            .map(|path| schema_base_name(path, schema_suffix(input_dir)))
+            .collect();
+        assert_eq!(names, vec!["user", "user.v2"]);
+
+        let types: Vec<_> = schema_files(input_dir)
+            .unwrap()
+            .iter()
+            .map(|path| {
+                let name = schema_base_name(path, ".schema.yaml");
+                let tokens = Generator::builder()
+                    .with_root_name_str(&name)
+                    .with_input_file(path)
+                    .build()
+                    .generate()
+                    .to_string();
+                let mut tokens = tokens.split_whitespace();
+                tokens.find(|token| *token == "struct");
+                tokens.next().unwrap().to_owned()
+            })
+            .collect();
+        assert_eq!(types, vec!["User", "UserV2"]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_generation_matches_serial() {
+        let schemas: Vec<_> = schema_files(Path::new("tests/dotted/*.schema.yaml"))
+            .unwrap()
+            .into_iter()
+            .chain(Some(PathBuf::from("tests/multiple-property-types.json")))
+            .map(|path| {
+                let name = schema_base_name(&path, ".schema.yaml");
+                (path, name)
+            })
+            .collect();
+        fn configure<'a, 'b>(
+            _: &Path,
+            builder: GeneratorBuilder<'a, 'b>,
+        ) -> GeneratorBuilder<'a, 'b> {
+            builder
+        }
+
+        let serial = generate_schemas(&schemas, &configure, false).unwrap();
+        let parallel = generate_schemas(&schemas, &configure, true).unwrap();
+        assert_eq!(parallel, serial);
+        assert!(serial.contains("struct UserV2"));
+    }
+
+    #[test]
+    fn combined_provenance_header() {
+        let schemas: Vec<_> = schema_files(Path::new("tests/dotted/*.schema.yaml"))
+            .unwrap()
+            .into_iter()
+            .map(|path| {
+                let name = schema_base_name(&path, ".schema.yaml");
+                (path, name)
+            })
+            .collect();
+        fn configure<'a, 'b>(
+            path: &Path,
+            builder: GeneratorBuilder<'a, 'b>,
+        ) -> GeneratorBuilder<'a, 'b> {
+            builder.with_provenance_header(path.ends_with("user.v2.schema.yaml"))
+        }
+
+        let out = generate_schemas(&schemas, &configure, false).unwrap();
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some("// Generated by schemafy from:"));
+        for (path, _) in &schemas {
+            assert_eq!(lines.next().unwrap(), format!("// - `{}`", path.display()));
+        }
+        assert!(lines.next().unwrap().starts_with("// Generated at 20"));
+        assert_eq!(lines.next(), Some(""));
+        assert!(!out.contains("//!"));
+    }
 }
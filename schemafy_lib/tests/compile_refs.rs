@@ -0,0 +1,33 @@
+use std::path::Path;
+
+/// Runs `compile` with `OUT_DIR` pointing at a fresh directory and returns
+/// the generated code with all whitespace removed.
+fn compiled(compile: impl FnOnce()) -> String {
+    let out_dir = std::env::temp_dir().join("schemafy_compile_refs");
+    std::fs::create_dir_all(&out_dir).unwrap();
+    std::env::set_var("OUT_DIR", &out_dir);
+    compile();
+    let generated = std::fs::read_to_string(out_dir.join("resource.rs")).unwrap();
+    std::fs::remove_dir_all(&out_dir).unwrap();
+    generated.split_whitespace().collect()
+}
+
+// Both cases live in one test, as they share `OUT_DIR`.
+#[test]
+fn refs_to_dotted_schema_files() {
+    let generated = compiled(|| schemafy_lib::compile_schemas("tests/refs/*.schema.yaml"));
+    assert!(generated.contains("pubstructAddressV2{pubstreet:String,}"));
+    assert!(generated.contains("pubaddress:AddressV2,"));
+    // `#/definitions/base` is resolved within `address.v2.schema.yaml`
+    assert!(generated.contains("pubstructContact{pubemail:String,pubname:String,"));
+
+    let generated = compiled(|| {
+        schemafy_lib::compile_schemas_named(
+            "tests/refs/*.schema.yaml",
+            |path: &Path| format!("api_{}", path.file_name().unwrap().to_str().unwrap()),
+            |_, builder| builder,
+        )
+    });
+    assert!(generated.contains("pubstructApiAddressV2SchemaYaml{"));
+    assert!(generated.contains("pubaddress:ApiAddressV2SchemaYaml,"));
+}
@@ -15,6 +15,61 @@ fn schema() {
     expander.expand(&schema);
 }
 
+#[test]
+fn multi_document_schemas() {
+    let tokens = schemafy_lib::Generator::builder()
+        .with_input_file("tests/multi-document.schema.yaml")
+        .build()
+        .generate_documents("title")
+        .to_string();
+
+    assert!(tokens.contains("pub struct FirstConfig"));
+    assert!(tokens.contains("pub struct SecondConfig"));
+    assert!(tokens.contains("pub enabled : bool"));
+}
+
+#[test]
+fn item_visibility() {
+    use schemafy_lib::Visibility;
+
+    let generate = |visibility| {
+        schemafy_lib::Generator::builder()
+            .with_root_name_str("Config")
+            .with_input_file("tests/dotted/user.schema.yaml")
+            .with_visibility(visibility)
+            .build()
+            .generate()
+            .to_string()
+    };
+
+    assert!(generate(Visibility::Public).contains("pub struct Config"));
+    let tokens = generate(Visibility::Crate);
+    assert!(tokens.contains("pub (crate) struct Config"));
+    let tokens = generate(Visibility::Private);
+    assert!(tokens.contains("] struct Config"));
+    assert!(!tokens.contains("pub struct"));
+}
+
+#[test]
+fn dry_run() {
+    use std::path::Path;
+
+    let pairs = schemafy_lib::compile_schemas_dry_run(Path::new("tests/dotted/*.schema.yaml"))
+        .unwrap();
+    let output = std::env::current_dir().unwrap().join("resource.rs");
+    let fixtures = std::env::current_dir().unwrap().join("tests/dotted");
+    assert_eq!(
+        pairs,
+        vec![
+            (fixtures.join("user.schema.yaml"), output.clone()),
+            (fixtures.join("user.v2.schema.yaml"), output),
+        ]
+    );
+
+    let missing = schemafy_lib::compile_schemas_dry_run(Path::new("missing/*.schema.yaml"));
+    assert_eq!(missing.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
 #[test]
 fn test_str_to_ident() {
     use proc_macro2::Span;
@@ -45,3 +100,48 @@ fn test_str_to_ident() {
         Ident::new("thieves_tools", Span::call_site())
     );
 }
+
+#[test]
+fn provenance_header() {
+    let output = std::env::temp_dir().join("schemafy_provenance_header.rs");
+    schemafy_lib::Generator::builder()
+        .with_root_name_str("Config")
+        .with_input_file("tests/dotted/user.schema.yaml")
+        .with_provenance_header(true)
+        .build()
+        .generate_to_file(&output)
+        .unwrap();
+    let generated = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+
+    let mut lines = generated.lines();
+    assert_eq!(
+        lines.next(),
+        Some("//! Generated by schemafy from `tests/dotted/user.schema.yaml`.")
+    );
+    let timestamp = lines.next().unwrap();
+    assert!(timestamp.starts_with("//! Generated at 20"));
+    assert!(timestamp.ends_with("Z."));
+    assert!(generated.contains("pub struct Config"));
+}
+
+#[test]
+fn feature_gate() {
+    use schemafy_lib::FeatureGate;
+
+    let generate = |feature_gate| {
+        schemafy_lib::Generator::builder()
+            .with_root_name_str("UserProfile")
+            .with_input_file("tests/dotted/user.schema.yaml")
+            .with_feature_gate(feature_gate)
+            .build()
+            .generate()
+            .to_string()
+    };
+
+    assert!(!generate(FeatureGate::Off).contains("cfg"));
+    let tokens = generate(FeatureGate::RootName);
+    assert!(tokens.starts_with("# [cfg (feature = \"user_profile\")] # [derive"));
+    let tokens = generate(FeatureGate::Feature("users".into()));
+    assert!(tokens.contains("# [cfg (feature = \"users\")]"));
+}
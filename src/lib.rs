@@ -53,7 +53,17 @@
 /// Generate Rust types from a JSON schema.
 ///
 /// If the `root` parameter is supplied, then a type will be
-/// generated from the root of the schema.
+/// generated from the root of the schema. Passing `derive_default: true`
+/// makes the generated types implement `Default`; required fields are
+/// left empty and schema `default` values only apply when deserializing.
+/// Passing `derive_builder: true` also generates a `<Type>Builder` with a
+/// setter per field, created through `<Type>::builder()`.
+/// `field_case: camel` or `field_case: pascal` names struct fields in that
+/// case instead of snake_case, renaming them back to the schema keys.
+/// `cfg_feature: "name"` compiles the generated items only when the cargo
+/// feature `name` is enabled.
+/// With the `schemars` feature enabled, the generated types also implement
+/// `schemars::JsonSchema`, which requires a dependency on `schemars`.
 ///
 /// ```rust
 /// extern crate serde;
@@ -81,6 +91,10 @@ pub fn schemafy(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     schemafy_lib::Generator::builder()
         .with_root_name(root_name)
         .with_input_file(&input_file)
+        .with_derive_default(def.derive_default)
+        .with_derive_builder(def.derive_builder)
+        .with_field_case(def.field_case)
+        .with_feature_gate(def.feature_gate)
         .build()
         .generate()
         .into()
@@ -88,23 +102,60 @@ pub fn schemafy(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
 struct Def {
     root: Option<String>,
+    derive_default: bool,
+    derive_builder: bool,
+    field_case: schemafy_lib::FieldCase,
+    feature_gate: schemafy_lib::FeatureGate,
     input_file: syn::LitStr,
 }
 
 impl syn::parse::Parse for Def {
     fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
-        let root = if input.peek(syn::Ident) {
-            let root_ident: syn::Ident = input.parse()?;
-            if root_ident != "root" {
-                return Err(syn::Error::new(root_ident.span(), "Expected `root`"));
-            }
+        let mut root = None;
+        let mut derive_default = false;
+        let mut derive_builder = false;
+        let mut field_case = schemafy_lib::FieldCase::Snake;
+        let mut feature_gate = schemafy_lib::FeatureGate::Off;
+        while input.peek(syn::Ident) {
+            let option: syn::Ident = input.parse()?;
             input.parse::<syn::Token![:]>()?;
-            Some(input.parse::<syn::Ident>()?.to_string())
-        } else {
-            None
-        };
+            if option == "root" {
+                root = Some(input.parse::<syn::Ident>()?.to_string());
+            } else if option == "derive_default" {
+                derive_default = input.parse::<syn::LitBool>()?.value;
+            } else if option == "derive_builder" {
+                derive_builder = input.parse::<syn::LitBool>()?.value;
+            } else if option == "field_case" {
+                let case: syn::Ident = input.parse()?;
+                field_case = if case == "snake" {
+                    schemafy_lib::FieldCase::Snake
+                } else if case == "camel" {
+                    schemafy_lib::FieldCase::Camel
+                } else if case == "pascal" {
+                    schemafy_lib::FieldCase::Pascal
+                } else {
+                    return Err(syn::Error::new(
+                        case.span(),
+                        "Expected `snake`, `camel` or `pascal`",
+                    ));
+                };
+            } else if option == "cfg_feature" {
+                let feature = input.parse::<syn::LitStr>()?.value();
+                feature_gate = schemafy_lib::FeatureGate::Feature(feature);
+            } else {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "Expected `root`, `derive_default`, `derive_builder`, `field_case` or \
+                     `cfg_feature`",
+                ));
+            }
+        }
         Ok(Def {
             root,
+            derive_default,
+            derive_builder,
+            field_case,
+            feature_gate,
             input_file: input.parse()?,
         })
     }
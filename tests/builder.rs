@@ -0,0 +1,40 @@
+use serde_derive::{Deserialize, Serialize};
+
+schemafy::schemafy!(
+    root: Server
+    derive_builder: true
+    "tests/builder.json"
+);
+
+#[test]
+fn builder_sets_fields() {
+    let server = Server::builder()
+        .name("local")
+        .user_tags(vec!["a".to_string()])
+        .port(8080)
+        .build()
+        .unwrap();
+    assert_eq!(server.name, "local");
+    assert_eq!(server.user_tags, ["a"]);
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.retries, 3);
+
+    let server = Server::builder()
+        .name("local")
+        .user_tags(vec![])
+        .port(8080)
+        .retries(5)
+        .build()
+        .unwrap();
+    assert_eq!(server.retries, 5);
+}
+
+#[test]
+fn builder_reports_missing_required_field() {
+    let err = Server::builder()
+        .name("local")
+        .user_tags(vec![])
+        .build()
+        .unwrap_err();
+    assert_eq!(err, "missing required field `port`");
+}
@@ -0,0 +1,30 @@
+use schemafy_core::yaml_value::YamlValue;
+use serde_derive::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+schemafy::schemafy!(
+    root: Config
+    derive_default: true
+    "tests/derive-default.json"
+);
+
+#[test]
+fn generated_types_implement_default() {
+    let config = Config::default();
+    assert_eq!(config.name, "");
+    assert_eq!(config.mode, Mode::Fast);
+    assert_eq!(config.level, Level::Low);
+    assert_eq!(config.extra, YamlValue::Null);
+    assert_eq!(config.target, Target::Variant0(TargetVariant0::default()));
+}
+
+#[test]
+fn schema_defaults_only_apply_when_deserializing() {
+    assert_eq!(Config::default().retries, 0);
+
+    let config: Config = serde_json::from_str(
+        r#"{ "name": "a", "mode": "safe", "level": 2, "target": { "host": "h" }, "extra": {} }"#,
+    )
+    .unwrap();
+    assert_eq!(config.retries, 3);
+}
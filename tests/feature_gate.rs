@@ -0,0 +1,27 @@
+#[cfg(feature = "feature-gate-test")]
+use serde_derive::{Deserialize, Serialize};
+
+schemafy::schemafy!(
+    root: Common
+    cfg_feature: "feature-gate-test"
+    "tests/refs/common.schema.yaml"
+);
+
+// Only compiles if the generated `Common` is left out without the feature.
+#[cfg(not(feature = "feature-gate-test"))]
+struct Common;
+
+#[cfg(not(feature = "feature-gate-test"))]
+#[test]
+fn gated_types_are_excluded_without_the_feature() {
+    assert_eq!(std::mem::size_of::<Common>(), 0);
+}
+
+// Run with `cargo test --features feature-gate-test --test feature_gate`.
+#[cfg(feature = "feature-gate-test")]
+#[test]
+fn gated_types_are_included_with_the_feature() {
+    let common: Common = serde_yaml::from_str("version: v1").unwrap();
+    assert_eq!(common.version, "v1");
+    assert_ne!(std::mem::size_of::<Common>(), 0);
+}
@@ -0,0 +1,115 @@
+use serde_derive::{Deserialize, Serialize};
+
+// Implemented by the generated types whose first required property is an
+// integer.
+pub trait Identifier {
+    fn key(&self) -> i64;
+}
+
+mod snake {
+    use super::*;
+
+    schemafy::schemafy!(
+        root: Settings
+        field_case: snake
+        "tests/camel-case-keys.json"
+    );
+}
+
+mod pascal {
+    use super::*;
+
+    schemafy::schemafy!(
+        root: Settings
+        field_case: pascal
+        "tests/camel-case-keys.json"
+    );
+}
+
+mod camel {
+    use super::*;
+
+    schemafy::schemafy!(
+        root: Settings
+        field_case: camel
+        "tests/snake-case-keys.json"
+    );
+}
+
+mod pascal_mixed {
+    use super::*;
+
+    schemafy::schemafy!(
+        root: Account
+        field_case: pascal
+        "tests/mixed-case-keys.json"
+    );
+}
+
+mod camel_mixed {
+    use super::*;
+
+    schemafy::schemafy!(
+        root: Account
+        field_case: camel
+        "tests/mixed-case-keys.json"
+    );
+}
+
+const YAML: &str = "userName: admin\nmaxRetryCount: 3\nport: 22\n";
+
+fn assert_round_trips<T: serde::Serialize>(value: &T) {
+    let original: serde_yaml::Value = serde_yaml::from_str(YAML).unwrap();
+    assert_eq!(serde_yaml::to_value(value).unwrap(), original);
+}
+
+#[test]
+fn camel_case_keys_become_snake_case_fields() {
+    let settings: snake::Settings = serde_yaml::from_str(YAML).unwrap();
+    assert_eq!(settings.user_name, "admin");
+    assert_eq!(settings.max_retry_count, 3);
+    assert_eq!(settings.port, 22);
+    assert_round_trips(&settings);
+}
+
+#[test]
+fn camel_case_keys_become_pascal_case_fields() {
+    let settings: pascal::Settings = serde_yaml::from_str(YAML).unwrap();
+    assert_eq!(settings.UserName, "admin");
+    assert_eq!(settings.MaxRetryCount, 3);
+    assert_eq!(settings.Port, 22);
+    assert_round_trips(&settings);
+}
+
+#[test]
+fn snake_case_keys_become_camel_case_fields() {
+    let yaml = "snake_key: admin\nmax_retry_count: 3\nport: 22\n";
+    let settings: camel::Settings = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(settings.snakeKey, "admin");
+    assert_eq!(settings.maxRetryCount, 3);
+    assert_eq!(settings.port, 22);
+
+    let original: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(serde_yaml::to_value(&settings).unwrap(), original);
+}
+
+const MIXED_YAML: &str = "someId: 7\nuser-name: admin\ntype: user\n";
+
+#[test]
+fn hyphenated_and_keyword_keys_follow_the_field_case() {
+    let original: serde_yaml::Value = serde_yaml::from_str(MIXED_YAML).unwrap();
+
+    let account: pascal_mixed::Account = serde_yaml::from_str(MIXED_YAML).unwrap();
+    assert_eq!(account.SomeId, 7);
+    assert_eq!(account.UserName, "admin");
+    assert_eq!(account.Type, "user");
+    assert_eq!(account.key(), 7);
+    assert_eq!(serde_yaml::to_value(&account).unwrap(), original);
+
+    let account: camel_mixed::Account = serde_yaml::from_str(MIXED_YAML).unwrap();
+    assert_eq!(account.someId, 7);
+    assert_eq!(account.userName, "admin");
+    assert_eq!(account.type_, "user");
+    assert_eq!(account.key(), 7);
+    assert_eq!(serde_yaml::to_value(&account).unwrap(), original);
+}
@@ -0,0 +1,26 @@
+use schemafy_core::de::FromYamlValue;
+use schemafy_core::yaml_value::YamlValue;
+use serde_derive::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+schemafy::schemafy!(
+    root: Config
+    "tests/derive-default.json"
+);
+
+#[test]
+fn decodes_generated_types_from_yaml_value() {
+    let yaml = "name: a\nmode: safe\nlevel: 2\ntarget: { path: /tmp }\nextra: { k: [1, 2] }\n";
+    let value: YamlValue = serde_yaml::from_str(yaml).unwrap();
+
+    let config = Config::from_yaml_value(&value).unwrap();
+    assert_eq!(config, serde_yaml::from_str::<Config>(yaml).unwrap());
+    assert_eq!(config.retries, 3);
+    assert_eq!(config.level, Level::High);
+    assert_eq!(
+        config.target,
+        Target::Variant1(TargetVariant1 {
+            path: "/tmp".into()
+        })
+    );
+}
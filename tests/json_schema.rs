@@ -0,0 +1,29 @@
+#![cfg(feature = "schemars")]
+
+use schemafy_core::yaml_value::YamlValue;
+use serde_derive::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+schemafy::schemafy!(
+    root: Config
+    "tests/derive-default.json"
+);
+
+#[test]
+fn generated_types_implement_json_schema() {
+    let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap();
+
+    let properties = &schema["properties"];
+    assert_eq!(properties["name"]["type"], "string");
+    assert_eq!(properties["retries"]["default"], 3);
+    assert_eq!(properties["extra"], true);
+
+    let definitions = &schema["definitions"];
+    assert_eq!(definitions["mode"]["enum"], serde_json::json!(["fast", "safe"]));
+    assert_eq!(definitions["level"]["type"], "integer");
+    assert_eq!(definitions["level"]["enum"], serde_json::json!([1, 2]));
+    assert_eq!(
+        definitions["Target"]["anyOf"][0]["$ref"],
+        "#/definitions/TargetVariant0"
+    );
+}
@@ -0,0 +1,21 @@
+use schemafy_core::yaml_value::YamlValue;
+use serde_derive::{Deserialize, Serialize};
+
+schemafy::schemafy!(
+    root: Account
+    "tests/hyphenated-keys.schema.yaml"
+);
+
+#[test]
+fn invalid_identifiers_are_renamed() {
+    let yaml = "user-name: alice\n2fa-enabled: true\nlogin.count: 3\nplain: x\n";
+    let account: Account = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(account.user_name, "alice");
+    assert!(account._2fa_enabled);
+    assert_eq!(account.login_count, 3);
+    assert_eq!(account.plain, "x");
+
+    let serialized = serde_yaml::to_string(&account).unwrap();
+    let round_trip: YamlValue = serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(round_trip, serde_yaml::from_str::<YamlValue>(yaml).unwrap());
+}
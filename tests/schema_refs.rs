@@ -0,0 +1,28 @@
+use serde_derive::{Deserialize, Serialize};
+
+schemafy::schemafy!(
+    root: Common
+    "tests/refs/common.schema.yaml"
+);
+
+schemafy::schemafy!(
+    root: User
+    "tests/refs/user.schema.yaml"
+);
+
+#[test]
+fn refs_to_other_schema_files() {
+    let user: User = serde_yaml::from_str(
+        "address: { street: Main }\ncommon: { version: v2 }\ncontact: { name: a, email: b }\n",
+    )
+    .unwrap();
+    assert_eq!(
+        user.address,
+        Address {
+            street: "Main".into()
+        }
+    );
+    assert_eq!(user.common.version, "v2");
+    assert_eq!(user.contact.name, "a");
+    assert_eq!(user.contact.email, "b");
+}
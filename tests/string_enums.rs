@@ -0,0 +1,47 @@
+use schemafy_core::yaml_value::YamlValue;
+use serde_derive::{Deserialize, Serialize};
+
+schemafy::schemafy!(
+    root: Task
+    "tests/string-enums.schema.yaml"
+);
+
+#[test]
+fn inline_string_enums_become_enums() {
+    let cases = [
+        ("todo", Taskstatus::Todo),
+        ("in-progress", Taskstatus::InProgress),
+        ("in_progress", Taskstatus::InProgress_),
+        ("done", Taskstatus::Done),
+        ("2nd-review", Taskstatus::V2NdReview),
+        ("''", Taskstatus::Empty),
+        ("self", Taskstatus::Self_),
+    ];
+    for (value, status) in cases.iter() {
+        let task: Task = serde_yaml::from_str(&format!("name: t\nstatus: {}\n", value)).unwrap();
+        assert_eq!(task.status, *status);
+
+        let serialized = serde_yaml::to_string(&task).unwrap();
+        let round_trip: YamlValue = serde_yaml::from_str(&serialized).unwrap();
+        assert_eq!(
+            round_trip.pointer("/status"),
+            serde_yaml::from_str(value).ok().as_ref()
+        );
+    }
+
+    let task: Task = serde_yaml::from_str("name: t\nstatus: done\ntags: [bug, feature]\n").unwrap();
+    assert_eq!(
+        task.tags,
+        Some(vec![TasktagsItem::Bug, TasktagsItem::Feature])
+    );
+    assert!(serde_yaml::from_str::<Task>("name: t\nstatus: blocked\n").is_err());
+}
+
+#[test]
+fn inline_string_enum_defaults() {
+    let task: Task = serde_yaml::from_str("name: t\nstatus: todo\n").unwrap();
+    assert_eq!(task.priority, Taskpriority::HighPriority);
+
+    let task: Task = serde_yaml::from_str("name: t\nstatus: todo\npriority: low\n").unwrap();
+    assert_eq!(task.priority, Taskpriority::Low);
+}